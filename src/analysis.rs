@@ -0,0 +1,199 @@
+use crate::{
+    program::{Program, SparseRow},
+    utils::normalize_row,
+};
+use std::collections::HashMap;
+
+/// Structural findings about a program, from `Program::analyze`. None of them
+/// prove a circuit unsound, but each is worth a look before trusting it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Analysis {
+    /// Constraints as given, before padding to the domain size.
+    pub constraints: usize,
+    pub rows: usize,
+    pub cols: usize,
+    /// Public inputs the verifier takes, not counting the constant one.
+    pub public: usize,
+    /// Non-zero entries across L, R and O, with repeated columns merged.
+    pub nonzero: usize,
+    /// Columns, other than the constant one, that no constraint references.
+    pub unused_columns: Vec<usize>,
+    /// Public columns that no constraint references, so a proof holds for
+    /// any value of them.
+    pub unused_public: Vec<usize>,
+    /// Private columns referenced by a single constraint, and only linearly
+    /// there. That constraint holds for any values of the other variables by
+    /// adjusting this one, so it pins down nothing else.
+    pub linear_only: Vec<usize>,
+    /// `(first, duplicate)` pairs of identical constraints, with L and R
+    /// taken in either order.
+    pub duplicate_constraints: Vec<(usize, usize)>,
+}
+
+impl Program {
+    pub fn analyze(&self) -> Analysis {
+        let constraints = self.left.len();
+        // Entries that are zero once merged reference nothing
+        let [left, right, output] = [&self.left, &self.right, &self.output]
+            .map(|rows| rows.iter().map(normalize_row).collect::<Vec<_>>());
+        let mut rows_of = vec![vec![]; self.cols];
+        let mut nonlinear = vec![false; self.cols];
+        for i in 0..constraints {
+            let rows = [&left[i], &right[i], &output[i]];
+            for row in rows {
+                for (col, _) in row.iter() {
+                    if rows_of[*col].last() != Some(&i) {
+                        rows_of[*col].push(i);
+                    }
+                }
+            }
+            // A column in L is multiplied by a variable unless R is constant, and vice versa
+            for (row, other) in [(rows[0], rows[1]), (rows[1], rows[0])] {
+                if other.iter().any(|(col, _)| *col != 0) {
+                    row.iter().for_each(|(col, _)| nonlinear[*col] = true);
+                }
+            }
+        }
+
+        let unused = |col: &usize| rows_of[*col].is_empty();
+        let mut seen = HashMap::new();
+        let mut duplicate_constraints = vec![];
+        for i in 0..constraints {
+            let (mut a, mut b) = (row_key(&left[i]), row_key(&right[i]));
+            if b < a {
+                (a, b) = (b, a);
+            }
+            let key = (a, b, row_key(&output[i]));
+            match seen.get(&key) {
+                Some(first) => duplicate_constraints.push((*first, i)),
+                None => {
+                    seen.insert(key, i);
+                }
+            }
+        }
+
+        Analysis {
+            constraints,
+            rows: self.rows,
+            cols: self.cols,
+            public: self.public.len() - 1,
+            nonzero: left.iter().chain(&right).chain(&output).map(Vec::len).sum(),
+            unused_columns: (1..self.cols).filter(unused).collect(),
+            unused_public: self.public[1..].iter().copied().filter(unused).collect(),
+            linear_only: (self.private_columns().into_iter())
+                .filter(|col| rows_of[*col].len() == 1 && !nonlinear[*col])
+                .collect(),
+            duplicate_constraints,
+        }
+    }
+}
+
+impl Analysis {
+    /// Lists the findings one per line, naming columns and constraints by
+    /// their labels in `program`.
+    pub fn report(&self, program: &Program) -> String {
+        let columns = |cols: &[usize]| {
+            cols.iter()
+                .map(|col| program.column_name(*col))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut lines = vec![format!(
+            "{} constraints over {} columns ({} public), {} non-zero entries",
+            self.constraints, self.cols, self.public, self.nonzero
+        )];
+        if !self.unused_columns.is_empty() {
+            lines.push(format!("unused columns: {}", columns(&self.unused_columns)));
+        }
+        if !self.unused_public.is_empty() {
+            lines.push(format!(
+                "unused public inputs: {}",
+                columns(&self.unused_public)
+            ));
+        }
+        if !self.linear_only.is_empty() {
+            lines.push(format!(
+                "only used linearly once: {}",
+                columns(&self.linear_only)
+            ));
+        }
+        for (first, duplicate) in &self.duplicate_constraints {
+            lines.push(format!(
+                "constraint `{}` duplicates `{}`",
+                program.constraint_name(*duplicate),
+                program.constraint_name(*first)
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// A normalized row with the coefficients as bytes, so it can be hashed.
+fn row_key(row: &SparseRow) -> Vec<(usize, [u8; 32])> {
+    row.iter()
+        .map(|(col, value)| (*col, value.to_bytes()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dsl::Circuit, field::from_i64};
+    use bls12_381::Scalar;
+
+    #[test]
+    fn analyze_free_variable() {
+        // Any y has some z with y^2 = 4x^3 + 2z + 9, which the analysis spots
+        let program = Circuit::parse("public y; private x, z; y^2 == 4*x^3 + 2*z + 9;")
+            .unwrap()
+            .program()
+            .unwrap();
+        assert_eq!(
+            program.analyze(),
+            Analysis {
+                constraints: 3,
+                rows: 4,
+                cols: 6,
+                public: 1,
+                nonzero: 11,
+                linear_only: vec![3],
+                ..Analysis::default()
+            }
+        );
+        assert_eq!(
+            program.analyze().report(&program),
+            "3 constraints over 6 columns (1 public), 11 non-zero entries\n\
+             only used linearly once: z"
+        );
+    }
+
+    #[test]
+    fn analyze_findings() {
+        // [1, a, b, x, y, t, u]: x * x = y twice (once swapped), b and u are
+        // never used, if only with zero coefficients, and t only appears in
+        // t + x = y, which pins nothing down
+        let one = Scalar::one();
+        let program = Program::new_sparse(
+            &[
+                vec![(3, one)],
+                vec![(3, one), (4, Scalar::zero()), (6, one), (6, -one)],
+                vec![(5, one), (3, one)],
+            ],
+            &[vec![(3, one)], vec![(3, one)], vec![(0, one)]],
+            &[
+                vec![(4, one), (1, one)],
+                vec![(1, one), (4, one)],
+                vec![(4, from_i64(2)), (4, -one)],
+            ],
+            7,
+            3,
+        )
+        .unwrap();
+        let analysis = program.analyze();
+        assert_eq!(analysis.unused_columns, [2, 6]);
+        assert_eq!(analysis.unused_public, [2]);
+        assert_eq!(analysis.linear_only, [5]);
+        assert_eq!(analysis.duplicate_constraints, [(0, 1)]);
+        assert_eq!(analysis.nonzero, 12);
+    }
+}
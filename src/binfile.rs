@@ -0,0 +1,214 @@
+//! The sectioned binary container shared by the snarkjs and Circom file
+//! formats (`.ptau`, `.zkey`, `.r1cs`, `.wtns`), and their encoding of field
+//! elements and curve points.
+
+use crate::serialize::DecodeError;
+use bls12_381::{G1Affine, G2Affine, Scalar};
+use num_bigint::BigUint;
+use std::sync::OnceLock;
+
+/// The BLS12-381 base field modulus q.
+pub(crate) const Q_HEX: &str = "1a0111ea397fe69a4b1ba7b6434bacd764774b84f38512bf6730d2a0f6b0f6241eabfffeb153ffffb9feffffffffaaab";
+
+/// The BLS12-381 scalar field modulus r.
+pub(crate) const R_HEX: &str = "73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+
+pub(crate) const FQ_SIZE: usize = 48;
+pub(crate) const FR_SIZE: usize = 32;
+
+pub(crate) fn q() -> &'static BigUint {
+    static Q: OnceLock<BigUint> = OnceLock::new();
+    Q.get_or_init(|| BigUint::parse_bytes(Q_HEX.as_bytes(), 16).unwrap())
+}
+
+pub(crate) fn r() -> &'static BigUint {
+    static R: OnceLock<BigUint> = OnceLock::new();
+    R.get_or_init(|| BigUint::parse_bytes(R_HEX.as_bytes(), 16).unwrap())
+}
+
+/// Parsed container: a magic string, a version and a list of typed sections.
+pub(crate) struct BinFile<'a> {
+    pub version: u32,
+    sections: Vec<(u32, &'a [u8])>,
+}
+
+impl<'a> BinFile<'a> {
+    pub fn parse(bytes: &'a [u8], magic: &[u8; 4]) -> Result<Self, DecodeError> {
+        let mut r = Cursor::new(bytes);
+        if r.take(4)? != magic {
+            return Err(DecodeError::BadMagic);
+        }
+        let version = r.u32()?;
+        let count = r.u32()?;
+        let mut sections = vec![];
+        for _ in 0..count {
+            let id = r.u32()?;
+            let size = r.u64()?;
+            let size: usize = size.try_into().map_err(|_| DecodeError::UnexpectedEof)?;
+            sections.push((id, r.take(size)?));
+        }
+        Ok(BinFile { version, sections })
+    }
+
+    pub fn section(&self, id: u32) -> Result<Cursor<'a>, DecodeError> {
+        self.sections
+            .iter()
+            .find(|(section, _)| *section == id)
+            .map(|(_, bytes)| Cursor::new(bytes))
+            .ok_or(DecodeError::MissingSection(id))
+    }
+}
+
+/// Assembles a container from `(id, data)` sections.
+pub(crate) fn write(magic: &[u8; 4], version: u32, sections: &[(u32, Vec<u8>)]) -> Vec<u8> {
+    let mut bytes = magic.to_vec();
+    bytes.extend_from_slice(&version.to_le_bytes());
+    bytes.extend_from_slice(&(sections.len() as u32).to_le_bytes());
+    for (id, data) in sections {
+        bytes.extend_from_slice(&id.to_le_bytes());
+        bytes.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(data);
+    }
+    bytes
+}
+
+pub(crate) struct Cursor<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Cursor { bytes }
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < len {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn u32(&mut self) -> Result<u32, DecodeError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, DecodeError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    /// Reads a length-prefixed little-endian integer and checks it is `expected`.
+    pub fn modulus(&mut self, expected: &BigUint) -> Result<usize, DecodeError> {
+        let size = self.u32()? as usize;
+        if BigUint::from_bytes_le(self.take(size)?) != *expected {
+            return Err(DecodeError::WrongCurve);
+        }
+        Ok(size)
+    }
+
+    /// A scalar in plain little-endian form.
+    pub fn fr(&mut self) -> Result<Scalar, DecodeError> {
+        let bytes: [u8; 32] = self.take(FR_SIZE)?.try_into().unwrap();
+        Option::from(Scalar::from_bytes(&bytes)).ok_or(DecodeError::InvalidScalar)
+    }
+
+    /// A G1 point as `x || y` in little-endian Montgomery form, all zeros for infinity.
+    pub fn g1(&mut self) -> Result<G1Affine, DecodeError> {
+        let bytes = self.take(2 * FQ_SIZE)?;
+        if bytes.iter().all(|b| *b == 0) {
+            return Ok(G1Affine::identity());
+        }
+        let mut uncompressed = [0u8; 96];
+        uncompressed[..48].copy_from_slice(&fq_to_be(&bytes[..48])?);
+        uncompressed[48..].copy_from_slice(&fq_to_be(&bytes[48..])?);
+        Option::from(G1Affine::from_uncompressed(&uncompressed)).ok_or(DecodeError::InvalidPoint)
+    }
+
+    /// A G2 point as `x.c0 || x.c1 || y.c0 || y.c1`, encoded like `g1`.
+    pub fn g2(&mut self) -> Result<G2Affine, DecodeError> {
+        let bytes = self.take(4 * FQ_SIZE)?;
+        if bytes.iter().all(|b| *b == 0) {
+            return Ok(G2Affine::identity());
+        }
+        // The bls12_381 crate orders each coordinate as c1 || c0
+        let mut uncompressed = [0u8; 192];
+        for (from, to) in [(0, 48), (48, 0), (96, 144), (144, 96)] {
+            uncompressed[to..to + 48].copy_from_slice(&fq_to_be(&bytes[from..from + 48])?);
+        }
+        Option::from(G2Affine::from_uncompressed(&uncompressed)).ok_or(DecodeError::InvalidPoint)
+    }
+
+    pub fn g1_vec(&mut self, len: usize) -> Result<Vec<G1Affine>, DecodeError> {
+        if len.saturating_mul(2 * FQ_SIZE) > self.remaining() {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        (0..len).map(|_| self.g1()).collect()
+    }
+
+    pub fn g2_vec(&mut self, len: usize) -> Result<Vec<G2Affine>, DecodeError> {
+        if len.saturating_mul(4 * FQ_SIZE) > self.remaining() {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        (0..len).map(|_| self.g2()).collect()
+    }
+}
+
+pub(crate) fn write_g1(bytes: &mut Vec<u8>, point: &G1Affine) {
+    if bool::from(point.is_identity()) {
+        bytes.extend_from_slice(&[0; 2 * FQ_SIZE]);
+        return;
+    }
+    let uncompressed = point.to_uncompressed();
+    bytes.extend_from_slice(&fq_from_be(&uncompressed[..48]));
+    bytes.extend_from_slice(&fq_from_be(&uncompressed[48..]));
+}
+
+pub(crate) fn write_g2(bytes: &mut Vec<u8>, point: &G2Affine) {
+    if bool::from(point.is_identity()) {
+        bytes.extend_from_slice(&[0; 4 * FQ_SIZE]);
+        return;
+    }
+    let uncompressed = point.to_uncompressed();
+    for from in [48, 0, 144, 96] {
+        bytes.extend_from_slice(&fq_from_be(&uncompressed[from..from + 48]));
+    }
+}
+
+/// Montgomery little-endian base field element to plain big-endian.
+fn fq_to_be(bytes: &[u8]) -> Result<[u8; 48], DecodeError> {
+    let value = from_montgomery(bytes, q(), 384).map_err(|_| DecodeError::InvalidPoint)?;
+    let mut res = [0u8; 48];
+    let be = value.to_bytes_be();
+    res[48 - be.len()..].copy_from_slice(&be);
+    Ok(res)
+}
+
+/// Plain big-endian base field element to Montgomery little-endian. The
+/// top bits of the first byte hold the bls12_381 encoding flags.
+fn fq_from_be(bytes: &[u8]) -> Vec<u8> {
+    let mut bytes = bytes.to_vec();
+    bytes[0] &= 0x1f;
+    to_montgomery(&BigUint::from_bytes_be(&bytes), q(), 384, FQ_SIZE)
+}
+
+fn from_montgomery(bytes: &[u8], modulus: &BigUint, bits: u64) -> Result<BigUint, DecodeError> {
+    let value = BigUint::from_bytes_le(bytes);
+    if value >= *modulus {
+        return Err(DecodeError::InvalidScalar);
+    }
+    let r_inv = (BigUint::from(1u8) << bits)
+        .modinv(modulus)
+        .expect("Montgomery radix is invertible");
+    Ok(value * r_inv % modulus)
+}
+
+fn to_montgomery(value: &BigUint, modulus: &BigUint, bits: u64, size: usize) -> Vec<u8> {
+    let mut bytes = ((value << bits) % modulus).to_bytes_le();
+    bytes.resize(size, 0);
+    bytes
+}
@@ -0,0 +1,309 @@
+use crate::{
+    program::{format_terms, Program, SparseRow},
+    utils::normalize_row,
+    Error,
+};
+use bls12_381::Scalar;
+use std::{
+    collections::HashMap,
+    ops::{Add, Mul, Neg, Sub},
+};
+
+/// A wire of the circuit. Public variables are laid out right after the
+/// constant one in the final witness, followed by the private variables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Variable {
+    One,
+    Public(usize),
+    Private(usize),
+}
+
+/// A weighted sum of variables, `c_1 * v_1 + c_2 * v_2 + ...`.
+#[derive(Clone, Debug, Default)]
+pub struct LinearCombination(pub Vec<(Variable, Scalar)>);
+
+impl From<Variable> for LinearCombination {
+    fn from(var: Variable) -> Self {
+        LinearCombination(vec![(var, Scalar::one())])
+    }
+}
+
+impl From<Scalar> for LinearCombination {
+    fn from(value: Scalar) -> Self {
+        LinearCombination(vec![(Variable::One, value)])
+    }
+}
+
+impl<T: Into<LinearCombination>> Add<T> for LinearCombination {
+    type Output = LinearCombination;
+
+    fn add(mut self, rhs: T) -> LinearCombination {
+        self.0.extend(rhs.into().0);
+        self
+    }
+}
+
+impl<T: Into<LinearCombination>> Sub<T> for LinearCombination {
+    type Output = LinearCombination;
+
+    fn sub(self, rhs: T) -> LinearCombination {
+        self + -rhs.into()
+    }
+}
+
+impl Neg for LinearCombination {
+    type Output = LinearCombination;
+
+    fn neg(self) -> LinearCombination {
+        self * -Scalar::one()
+    }
+}
+
+impl Mul<Scalar> for LinearCombination {
+    type Output = LinearCombination;
+
+    fn mul(self, rhs: Scalar) -> LinearCombination {
+        LinearCombination(self.0.into_iter().map(|(v, c)| (v, c * rhs)).collect())
+    }
+}
+
+impl<T: Into<LinearCombination>> Add<T> for Variable {
+    type Output = LinearCombination;
+
+    fn add(self, rhs: T) -> LinearCombination {
+        LinearCombination::from(self) + rhs
+    }
+}
+
+impl<T: Into<LinearCombination>> Sub<T> for Variable {
+    type Output = LinearCombination;
+
+    fn sub(self, rhs: T) -> LinearCombination {
+        LinearCombination::from(self) - rhs
+    }
+}
+
+impl Neg for Variable {
+    type Output = LinearCombination;
+
+    fn neg(self) -> LinearCombination {
+        -LinearCombination::from(self)
+    }
+}
+
+impl Mul<Scalar> for Variable {
+    type Output = LinearCombination;
+
+    fn mul(self, rhs: Scalar) -> LinearCombination {
+        LinearCombination::from(self) * rhs
+    }
+}
+
+/// Builds a `Program` and its witness together from `a * b = c` constraints.
+#[derive(Default)]
+pub struct ConstraintSystem {
+    public: Vec<Scalar>,
+    private: Vec<Scalar>,
+    left: Vec<LinearCombination>,
+    right: Vec<LinearCombination>,
+    output: Vec<LinearCombination>,
+    variable_labels: HashMap<Variable, String>,
+    constraint_labels: Vec<Option<String>>,
+}
+
+impl ConstraintSystem {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The constant one, always held by the first witness column.
+    pub fn one(&self) -> Variable {
+        Variable::One
+    }
+
+    pub fn alloc_public(&mut self, value: Scalar) -> Variable {
+        self.public.push(value);
+        Variable::Public(self.public.len() - 1)
+    }
+
+    pub fn alloc_private(&mut self, value: Scalar) -> Variable {
+        self.private.push(value);
+        Variable::Private(self.private.len() - 1)
+    }
+
+    /// The value assigned to a linear combination by the recorded witness.
+    pub fn value(&self, lc: impl Into<LinearCombination>) -> Scalar {
+        lc.into()
+            .0
+            .iter()
+            .map(|(var, coeff)| {
+                coeff
+                    * match var {
+                        Variable::One => Scalar::one(),
+                        Variable::Public(i) => self.public[*i],
+                        Variable::Private(i) => self.private[*i],
+                    }
+            })
+            .sum()
+    }
+
+    /// Names a variable, which becomes the label of its witness column.
+    pub fn label(&mut self, var: Variable, label: impl Into<String>) {
+        self.variable_labels.insert(var, label.into());
+    }
+
+    /// Adds the constraint `a * b = c`.
+    pub fn enforce(
+        &mut self,
+        a: impl Into<LinearCombination>,
+        b: impl Into<LinearCombination>,
+        c: impl Into<LinearCombination>,
+    ) {
+        self.left.push(a.into());
+        self.right.push(b.into());
+        self.output.push(c.into());
+        self.constraint_labels.push(None);
+    }
+
+    /// Adds the constraint `a * b = c` with a label for error messages.
+    pub fn enforce_labeled(
+        &mut self,
+        a: impl Into<LinearCombination>,
+        b: impl Into<LinearCombination>,
+        c: impl Into<LinearCombination>,
+        label: impl Into<String>,
+    ) {
+        self.enforce(a, b, c);
+        *self.constraint_labels.last_mut().unwrap() = Some(label.into());
+    }
+
+    /// Writes out a linear combination using the variable labels.
+    pub fn describe(&self, lc: &LinearCombination) -> String {
+        let mut terms: Vec<(Variable, Scalar)> = vec![];
+        for (var, coeff) in &lc.0 {
+            match terms.iter_mut().find(|(v, _)| v == var) {
+                Some((_, value)) => *value += coeff,
+                None => terms.push((*var, *coeff)),
+            }
+        }
+        let name = |var: Variable| match (var, self.variable_labels.get(&var)) {
+            (Variable::One, _) => None,
+            (_, Some(label)) => Some(label.clone()),
+            (Variable::Public(i), None) => Some(format!("public{}", i)),
+            (Variable::Private(i), None) => Some(format!("private{}", i)),
+        };
+        format_terms(
+            terms
+                .into_iter()
+                .filter(|(_, coeff)| *coeff != Scalar::zero())
+                .map(|(var, coeff)| (name(var), coeff)),
+        )
+    }
+
+    /// Lays out the witness as `[1, public..., private...]` and returns the
+    /// program together with the witness recorded while building it.
+    pub fn finish(self) -> Result<(Program, Vec<Scalar>), Error> {
+        let public = self.public.len() + 1;
+        let cols = public + self.private.len();
+
+        let mut program = Program::new_sparse(
+            &self.to_rows(&self.left),
+            &self.to_rows(&self.right),
+            &self.to_rows(&self.output),
+            cols,
+            public,
+        )?;
+        for (var, label) in &self.variable_labels {
+            program.column_labels[self.column(*var)] = Some(label.clone());
+        }
+        program.constraint_labels = self.constraint_labels;
+
+        let mut witness = vec![Scalar::one()];
+        witness.extend(self.public);
+        witness.extend(self.private);
+        Ok((program, witness))
+    }
+
+    fn column(&self, var: Variable) -> usize {
+        match var {
+            Variable::One => 0,
+            Variable::Public(i) => i + 1,
+            Variable::Private(i) => i + 1 + self.public.len(),
+        }
+    }
+
+    fn to_rows(&self, lcs: &[LinearCombination]) -> Vec<SparseRow> {
+        lcs.iter()
+            .map(|lc| {
+                let row: SparseRow =
+                    lc.0.iter()
+                        .map(|(var, coeff)| (self.column(*var), *coeff))
+                        .collect();
+                normalize_row(&row)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prover::Prover, setup::TrustedSetup, verifier::Verifier};
+
+    // y^2 = 4x^3 + 2z + 9
+    fn build(y: u64, x: u64, z: u64) -> (Program, Vec<Scalar>) {
+        let mut cs = ConstraintSystem::new();
+        let y = cs.alloc_public(Scalar::from(y));
+        let x = cs.alloc_private(Scalar::from(x));
+        let z = cs.alloc_private(Scalar::from(z));
+
+        let v1 = cs.alloc_private(cs.value(y) * cs.value(y));
+        cs.enforce(y, y, v1);
+        let v2 = cs.alloc_private(cs.value(x) * cs.value(x));
+        cs.enforce(x, x, v2);
+        cs.enforce(
+            v2,
+            x * Scalar::from(4),
+            v1 - z * Scalar::from(2) - Scalar::from(9),
+        );
+
+        cs.finish().unwrap()
+    }
+
+    #[test]
+    fn circuit_layout() {
+        let (program, witness) = build(5, 1, 6);
+        assert_eq!(program.cols, 6);
+        assert_eq!(program.public, [0, 1]);
+        assert_eq!(witness, [1, 5, 1, 6, 25, 1].map(Scalar::from).to_vec());
+        assert_eq!(
+            program.output[2],
+            vec![
+                (0, -Scalar::from(9)),
+                (3, -Scalar::from(2)),
+                (4, Scalar::one())
+            ]
+        );
+    }
+
+    #[test]
+    fn circuit_merges_terms() {
+        let mut cs = ConstraintSystem::new();
+        let x = cs.alloc_private(Scalar::from(3));
+        cs.enforce(x + x - x * Scalar::from(2) + cs.one(), x, x);
+
+        let (program, _) = cs.finish().unwrap();
+        assert_eq!(program.left[0], vec![(0, Scalar::one())]);
+    }
+
+    #[test]
+    fn circuit_prove() {
+        let (program, witness) = build(7, 1, 18);
+        let (proving_key, verifying_key) = TrustedSetup::new(&program);
+        let verifier = Verifier::new(verifying_key);
+        let prover = Prover::new(program, proving_key).unwrap();
+
+        let proof = prover.prove(&witness).unwrap();
+        assert_eq!(verifier.verify(&proof, &witness[1..2]), Ok(true));
+    }
+}
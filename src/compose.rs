@@ -0,0 +1,218 @@
+//! Builds one program out of two by concatenating their constraints, with
+//! chosen columns of the second identified with columns of the first, e.g.
+//! feeding the output of one gadget into the input of another.
+
+use crate::{
+    program::{Program, SparseRow},
+    Error,
+};
+use bls12_381::Scalar;
+
+/// The combined program and where the columns of each part ended up in it.
+pub struct Composition {
+    pub program: Program,
+    /// `first_columns[i]` is the combined column of column `i` of the first
+    /// program, which keeps its position.
+    pub first_columns: Vec<usize>,
+    /// `second_columns[i]` is the combined column of column `i` of the second
+    /// program. Linked columns map onto the first program's, and the rest
+    /// follow its columns in order.
+    pub second_columns: Vec<usize>,
+}
+
+impl Program {
+    /// Combines `self` and `second` into one program whose constraints are
+    /// those of `self` followed by those of `second`. Each `(first, second)`
+    /// pair in `links` makes the two columns one variable. A column of `self`
+    /// may feed several columns of `second`, but each column of `second` is
+    /// linked at most once. The constant columns are always shared.
+    ///
+    /// The public inputs are those of `self` followed by those of `second`
+    /// that are not already among them. Use `Program::set_public` on the
+    /// result to choose otherwise, e.g. to hide a linked output.
+    pub fn compose(
+        &self,
+        second: &Program,
+        links: &[(usize, usize)],
+    ) -> Result<Composition, Error> {
+        let mut second_columns = vec![None; second.cols];
+        second_columns[0] = Some(0);
+        for (a, b) in links {
+            if *a == 0
+                || *b == 0
+                || *a >= self.cols
+                || *b >= second.cols
+                || second_columns[*b].is_some()
+            {
+                return Err(Error::InvalidLink {
+                    first: *a,
+                    second: *b,
+                });
+            }
+            second_columns[*b] = Some(*a);
+        }
+        let mut cols = self.cols;
+        let second_columns: Vec<usize> = second_columns
+            .into_iter()
+            .map(|col| {
+                col.unwrap_or_else(|| {
+                    cols += 1;
+                    cols - 1
+                })
+            })
+            .collect();
+
+        let renumber = |rows: &[SparseRow]| -> Vec<SparseRow> {
+            rows.iter()
+                .map(|row| {
+                    row.iter()
+                        .map(|(col, c)| (second_columns[*col], *c))
+                        .collect()
+                })
+                .collect()
+        };
+        let left = [self.left.clone(), renumber(&second.left)].concat();
+        let right = [self.right.clone(), renumber(&second.right)].concat();
+        let output = [self.output.clone(), renumber(&second.output)].concat();
+        let mut program = Program::new_sparse(&left, &right, &output, cols, 1)?;
+
+        let mut public = self.public[1..].to_vec();
+        for col in second.public[1..].iter().map(|col| second_columns[*col]) {
+            if !public.contains(&col) {
+                public.push(col);
+            }
+        }
+        program.set_public(&public)?;
+
+        // A linked column keeps the first program's label unless it has none
+        program.column_labels[..self.cols].clone_from_slice(&self.column_labels);
+        for (col, label) in second_columns.iter().zip(&second.column_labels) {
+            if program.column_labels[*col].is_none() {
+                program.column_labels[*col] = label.clone();
+            }
+        }
+        program.constraint_labels = [
+            self.constraint_labels.clone(),
+            second.constraint_labels.clone(),
+        ]
+        .concat();
+
+        Ok(Composition {
+            program,
+            first_columns: (0..self.cols).collect(),
+            second_columns,
+        })
+    }
+}
+
+impl Composition {
+    /// Merges witnesses of the two parts into one for the combined program.
+    /// Linked columns take the first witness's value, and the result is
+    /// checked, so parts that disagree on a linked value are rejected.
+    pub fn witness(&self, first: &[Scalar], second: &[Scalar]) -> Result<Vec<Scalar>, Error> {
+        for (witness, columns) in [(first, &self.first_columns), (second, &self.second_columns)] {
+            if witness.len() != columns.len() {
+                return Err(Error::WitnessSizeMismatch {
+                    expected: columns.len(),
+                    found: witness.len(),
+                });
+            }
+        }
+        let mut witness = vec![Scalar::zero(); self.program.cols];
+        for (value, col) in second.iter().zip(&self.second_columns) {
+            witness[*col] = *value;
+        }
+        for (value, col) in first.iter().zip(&self.first_columns) {
+            witness[*col] = *value;
+        }
+        self.program.check_witness(&witness)?;
+        Ok(witness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        dsl::Circuit, field::from_i64, prover::Prover, setup::TrustedSetup, verifier::Verifier,
+    };
+
+    fn witness(values: &[i64]) -> Vec<Scalar> {
+        values.iter().map(|v| from_i64(*v)).collect()
+    }
+
+    #[test]
+    fn compose_chains_programs() {
+        // y = x^2 + 1 feeding z = y * w, i.e. [1, y, x] and [1, z, y, w]
+        let square = Circuit::parse("public y; private x; y == x^2 + 1;").unwrap();
+        let product = Circuit::parse("public z; private y, w; z == y * w;").unwrap();
+        let (first, second) = (square.program().unwrap(), product.program().unwrap());
+        let composition = first.compose(&second, &[(1, 2)]).unwrap();
+        let program = &composition.program;
+
+        assert_eq!(composition.second_columns, [0, 3, 1, 4]);
+        assert_eq!((program.cols, program.left.len()), (5, 2));
+        assert_eq!(program.public, [0, 1, 3]);
+        let names: Vec<_> = (1..program.cols).map(|c| program.column_name(c)).collect();
+        assert_eq!(names, ["y", "x", "z", "w"]);
+
+        // x = 3, w = 2 gives y = 10 and z = 20
+        let full = composition
+            .witness(&witness(&[1, 10, 3]), &witness(&[1, 20, 10, 2]))
+            .unwrap();
+        assert_eq!(full, witness(&[1, 10, 3, 20, 2]));
+        assert!(matches!(
+            composition.witness(&witness(&[1, 10, 3]), &witness(&[1, 22, 11, 2])),
+            Err(Error::BadWitness { rows }) if rows.len() == 1 && rows[0].row == 1
+        ));
+
+        // Hide the intermediate y and prove the chain from x to z
+        let mut program = composition.program;
+        program.set_public(&[3]).unwrap();
+        let (pk, vk) = TrustedSetup::new(&program);
+        let inputs = program.public_inputs(&full).unwrap();
+        let proof = Prover::new(program, pk).unwrap().prove(&full).unwrap();
+        assert_eq!(Verifier::new(vk).verify(&proof, &inputs), Ok(true));
+        assert_eq!(inputs, witness(&[20]));
+    }
+
+    #[test]
+    fn compose_fans_out() {
+        // y = x^2 + 1 feeding both inputs of z = a * b, so z = y * y
+        let square = Circuit::parse("public y; private x; y == x^2 + 1;").unwrap();
+        let product = Circuit::parse("public z; private a, b; z == a * b;").unwrap();
+        let (first, second) = (square.program().unwrap(), product.program().unwrap());
+        let composition = first.compose(&second, &[(1, 2), (1, 3)]).unwrap();
+        assert_eq!(composition.second_columns, [0, 3, 1, 1]);
+        assert_eq!(composition.program.cols, 4);
+
+        let full = composition
+            .witness(&witness(&[1, 10, 3]), &witness(&[1, 100, 10, 10]))
+            .unwrap();
+        assert_eq!(full, witness(&[1, 10, 3, 100]));
+        assert!(composition
+            .witness(&witness(&[1, 10, 3]), &witness(&[1, 110, 10, 11]))
+            .is_err());
+    }
+
+    #[test]
+    fn compose_rejects_bad_links() {
+        let program = Circuit::parse("public y; private x; y == x^2;")
+            .unwrap()
+            .program()
+            .unwrap();
+        for (first, second) in [(0, 1), (1, 0), (3, 1), (1, 3)] {
+            assert!(matches!(
+                program.compose(&program, &[(first, second)]),
+                Err(Error::InvalidLink { .. })
+            ));
+        }
+        assert!(matches!(
+            program.compose(&program, &[(1, 2), (2, 2)]),
+            Err(Error::InvalidLink {
+                first: 2,
+                second: 2
+            })
+        ));
+    }
+}
@@ -0,0 +1,322 @@
+use crate::{poly::Poly, Error};
+use bls12_381::Scalar;
+use ff::PrimeField;
+use std::ops::{Add, Mul, Sub};
+
+/// Anything an FFT can run over: scalars, or curve points to transform
+/// polynomials "in the exponent".
+pub trait FftElement:
+    Copy + Default + Add<Output = Self> + Sub<Output = Self> + Mul<Scalar, Output = Self>
+{
+}
+
+impl<T> FftElement for T where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T> + Mul<Scalar, Output = T>
+{
+}
+
+/// `(r - 1) / 2^32`, the odd part of the scalar field's multiplicative order.
+const ODD_ORDER: [u64; 4] = [
+    0xfffe5bfeffffffff,
+    0x09a1d80553bda402,
+    0x299d7d483339d808,
+    0x0000000073eda753,
+];
+
+/// Multiplicative subgroup of size `2^k` generated by a root of unity of the
+/// scalar field, used to move polynomials between coefficient and evaluation
+/// form in `O(n log n)`.
+///
+/// The roots of unity are derived from the smallest quadratic non-residue, 5,
+/// as snarkjs does, so row `j` of a program sits at the same point as
+/// constraint `j` in snarkjs's keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct EvaluationDomain {
+    pub size: usize,
+    log_size: u32,
+    pub omega: Scalar,
+    omega_inv: Scalar,
+    size_inv: Scalar,
+}
+
+impl EvaluationDomain {
+    /// Smallest domain with at least `min_size` elements. The field only has
+    /// roots of unity up to order `2^32`.
+    pub fn new(min_size: usize) -> Result<Self, Error> {
+        let size = min_size
+            .max(1)
+            .checked_next_power_of_two()
+            .filter(|size| size.trailing_zeros() <= Scalar::S)
+            .ok_or(Error::DomainTooLarge { size: min_size })?;
+        let log_size = size.trailing_zeros();
+
+        let omega = root_of_unity(log_size);
+        Ok(EvaluationDomain {
+            size,
+            log_size,
+            omega,
+            omega_inv: omega.invert().unwrap(),
+            size_inv: Scalar::from(size as u64).invert().unwrap(),
+        })
+    }
+
+    /// The `i`-th element of the domain, `omega^i`.
+    pub fn element(&self, i: usize) -> Scalar {
+        self.omega.pow_vartime(&[i as u64, 0, 0, 0])
+    }
+
+    /// The vanishing polynomial of the domain, `x^n - 1`.
+    pub fn vanishing_poly(&self) -> Poly {
+        let mut coeffs = vec![Scalar::zero(); self.size + 1];
+        coeffs[0] = -Scalar::one();
+        coeffs[self.size] = Scalar::one();
+        Poly::new(coeffs)
+    }
+
+    /// Evaluates every Lagrange basis polynomial of the domain at `tau`, so that
+    /// `sum(L_j(tau) * values[j])` is the interpolated polynomial of `values` at `tau`.
+    pub fn lagrange_coefficients(&self, tau: Scalar) -> Vec<Scalar> {
+        let t_tau = tau.pow_vartime(&[self.size as u64, 0, 0, 0]) - Scalar::one();
+        if t_tau == Scalar::zero() {
+            return (0..self.size)
+                .map(|i| {
+                    if self.element(i) == tau {
+                        Scalar::one()
+                    } else {
+                        Scalar::zero()
+                    }
+                })
+                .collect();
+        }
+
+        // L_j(tau) = omega^j * (tau^n - 1) / (n * (tau - omega^j))
+        let mut omega_j = Scalar::one();
+        (0..self.size)
+            .map(|_| {
+                let res = omega_j * t_tau * self.size_inv * (tau - omega_j).invert().unwrap();
+                omega_j *= self.omega;
+                res
+            })
+            .collect()
+    }
+
+    /// Evaluates the polynomial with the given coefficients on every element
+    /// of the domain.
+    pub fn fft<T: FftElement>(&self, coeffs: &[T]) -> Result<Vec<T>, Error> {
+        let mut values = self.resize(coeffs)?;
+        fft_in_place(&mut values, self.omega, self.log_size);
+        Ok(values)
+    }
+
+    /// Interpolates the polynomial taking the given values on the domain and
+    /// returns its coefficients.
+    pub fn ifft<T: FftElement>(&self, evaluations: &[T]) -> Result<Vec<T>, Error> {
+        let mut values = self.resize(evaluations)?;
+        fft_in_place(&mut values, self.omega_inv, self.log_size);
+        Ok(values.into_iter().map(|v| v * self.size_inv).collect())
+    }
+
+    /// Evaluates the polynomial with the given coefficients on the coset
+    /// `g * omega^i`, where `g` is the multiplicative generator of the field.
+    pub fn coset_fft(&self, coeffs: &[Scalar]) -> Result<Vec<Scalar>, Error> {
+        let mut shift = Scalar::one();
+        let coeffs: Vec<Scalar> = coeffs
+            .iter()
+            .map(|c| {
+                let res = c * shift;
+                shift *= Scalar::MULTIPLICATIVE_GENERATOR;
+                res
+            })
+            .collect();
+        self.fft(&coeffs)
+    }
+
+    /// Inverse of `coset_fft`.
+    pub fn coset_ifft(&self, evaluations: &[Scalar]) -> Result<Vec<Scalar>, Error> {
+        let g_inv = Scalar::MULTIPLICATIVE_GENERATOR.invert().unwrap();
+        let mut shift = Scalar::one();
+        Ok(self
+            .ifft(evaluations)?
+            .into_iter()
+            .map(|c| {
+                let res = c * shift;
+                shift *= g_inv;
+                res
+            })
+            .collect())
+    }
+
+    /// Divides evaluations on the coset by the vanishing polynomial, which
+    /// takes the same non-zero value `g^n - 1` on every element of the coset.
+    pub fn divide_by_vanishing_poly_on_coset(&self, evaluations: &mut [Scalar]) {
+        let t_inv = (Scalar::MULTIPLICATIVE_GENERATOR.pow_vartime(&[self.size as u64, 0, 0, 0])
+            - Scalar::one())
+        .invert()
+        .unwrap();
+        evaluations.iter_mut().for_each(|v| *v *= t_inv);
+    }
+
+    fn resize<T: FftElement>(&self, values: &[T]) -> Result<Vec<T>, Error> {
+        if values.len() > self.size {
+            return Err(Error::DomainTooSmall {
+                size: self.size,
+                found: values.len(),
+            });
+        }
+        let mut values = values.to_vec();
+        values.resize(self.size, T::default());
+        Ok(values)
+    }
+}
+
+fn fft_in_place<T: FftElement>(values: &mut [T], omega: Scalar, log_size: u32) {
+    let size = values.len();
+    for i in 0..size {
+        let j = bit_reverse(i, log_size);
+        if i < j {
+            values.swap(i, j);
+        }
+    }
+
+    let mut half = 1;
+    for _ in 0..log_size {
+        let step = omega.pow_vartime(&[(size / (2 * half)) as u64, 0, 0, 0]);
+        for start in (0..size).step_by(2 * half) {
+            let mut w = Scalar::one();
+            for k in 0..half {
+                let t = values[start + k + half] * w;
+                values[start + k + half] = values[start + k] - t;
+                values[start + k] = values[start + k] + t;
+                w *= step;
+            }
+        }
+        half *= 2;
+    }
+}
+
+fn bit_reverse(mut i: usize, bits: u32) -> usize {
+    let mut res = 0;
+    for _ in 0..bits {
+        res = (res << 1) | (i & 1);
+        i >>= 1;
+    }
+    res
+}
+
+/// Primitive `2^log_size`-th root of unity, `5^((r - 1) / 2^log_size)`.
+pub(crate) fn root_of_unity(log_size: u32) -> Scalar {
+    let mut omega = Scalar::from(5).pow_vartime(&ODD_ORDER);
+    for _ in log_size..Scalar::S {
+        omega = omega.square();
+    }
+    omega
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn domain_size() {
+        assert_eq!(EvaluationDomain::new(0).unwrap().size, 1);
+        assert_eq!(EvaluationDomain::new(3).unwrap().size, 4);
+        assert_eq!(EvaluationDomain::new(8).unwrap().size, 8);
+
+        let domain = EvaluationDomain::new(8).unwrap();
+        assert_eq!(domain.element(8), Scalar::one());
+        assert_ne!(domain.element(4), Scalar::one());
+
+        assert_eq!(
+            EvaluationDomain::new((1 << 32) + 1),
+            Err(Error::DomainTooLarge {
+                size: (1 << 32) + 1
+            })
+        );
+        assert_eq!(
+            domain.fft(&[Scalar::one(); 9]),
+            Err(Error::DomainTooSmall { size: 8, found: 9 })
+        );
+    }
+
+    #[test]
+    fn domain_fft() {
+        let domain = EvaluationDomain::new(4).unwrap();
+        let poly = Poly::new(vec![
+            Scalar::from(3),
+            Scalar::from(1),
+            Scalar::from(4),
+            Scalar::from(1),
+        ]);
+
+        let evaluations = domain.fft(&poly.0).unwrap();
+        for (i, value) in evaluations.iter().enumerate() {
+            assert_eq!(*value, poly.eval(domain.element(i)));
+        }
+        assert_eq!(Poly::new(domain.ifft(&evaluations).unwrap()), poly);
+    }
+
+    #[test]
+    fn domain_ifft_matches_interpolate() {
+        let domain = EvaluationDomain::new(4).unwrap();
+        let evaluations: Vec<Scalar> = (0..4).map(|i| Scalar::from(i * i + 7)).collect();
+        let points = (0..4).map(|i| domain.element(i)).collect();
+
+        assert_eq!(
+            Poly::new(domain.ifft(&evaluations).unwrap()),
+            Poly::interpolate(points, evaluations)
+        );
+    }
+
+    #[test]
+    fn domain_fft_in_exponent() {
+        let domain = EvaluationDomain::new(4).unwrap();
+        let coeffs: Vec<Scalar> = (0..4).map(|i| Scalar::from(i + 2)).collect();
+        let g1 = bls12_381::G1Projective::generator();
+
+        let points: Vec<_> = coeffs.iter().map(|c| g1 * c).collect();
+        let expected: Vec<_> = domain
+            .fft(&coeffs)
+            .unwrap()
+            .iter()
+            .map(|e| g1 * e)
+            .collect();
+        assert_eq!(domain.fft(&points).unwrap(), expected);
+        assert_eq!(domain.ifft(&expected).unwrap(), points);
+    }
+
+    #[test]
+    fn domain_lagrange_coefficients() {
+        let domain = EvaluationDomain::new(4).unwrap();
+        let evaluations: Vec<Scalar> = (0..4).map(|i| Scalar::from(3 * i + 1)).collect();
+        let poly = Poly::new(domain.ifft(&evaluations).unwrap());
+
+        for tau in [Scalar::from(11), domain.element(2)] {
+            let lagrange = domain.lagrange_coefficients(tau);
+            let value: Scalar = lagrange.iter().zip(&evaluations).map(|(l, e)| l * e).sum();
+            assert_eq!(value, poly.eval(tau));
+        }
+    }
+
+    #[test]
+    fn domain_coset_fft() {
+        let domain = EvaluationDomain::new(4).unwrap();
+        let poly = Poly::new(vec![Scalar::from(2), Scalar::from(7), Scalar::from(1)]);
+        let g = Scalar::MULTIPLICATIVE_GENERATOR;
+
+        let evaluations = domain.coset_fft(&poly.0).unwrap();
+        for (i, value) in evaluations.iter().enumerate() {
+            assert_eq!(*value, poly.eval(g * domain.element(i)));
+        }
+        assert_eq!(Poly::new(domain.coset_ifft(&evaluations).unwrap()), poly);
+    }
+
+    #[test]
+    fn domain_vanishing_poly() {
+        let domain = EvaluationDomain::new(4).unwrap();
+        let t = domain.vanishing_poly();
+        for i in 0..4 {
+            assert_eq!(t.eval(domain.element(i)), Scalar::zero());
+        }
+        assert_ne!(t.eval(Scalar::from(5)), Scalar::zero());
+    }
+}
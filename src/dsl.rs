@@ -0,0 +1,650 @@
+//! A small language for arithmetic circuits:
+//!
+//! ```text
+//! public y;
+//! private x, z;
+//! y^2 == 4*x^3 + 2*z + 9;
+//! ```
+//!
+//! Inputs are declared before use, and every `==` assertion is flattened into
+//! `a * b = c` constraints over the inputs and intermediate variables.
+
+use crate::{
+    circuit::{ConstraintSystem, LinearCombination, Variable},
+    field::from_dec_str,
+    program::{format_product, Program},
+    Error,
+};
+use bls12_381::Scalar;
+use num_bigint::BigUint;
+use std::{collections::HashMap, fmt};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Constant(Scalar),
+    Input(usize),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Pow(Box<Expr>, u64),
+}
+
+/// A parsed circuit. Inputs are numbered in declaration order, separately
+/// for public and private ones.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Circuit {
+    pub public: Vec<String>,
+    pub private: Vec<String>,
+    inputs: Vec<Variable>,
+    assertions: Vec<(Expr, Expr)>,
+}
+
+impl Circuit {
+    pub fn parse(source: &str) -> Result<Circuit, ParseError> {
+        Parser::new(source)?.circuit()
+    }
+
+    /// Compiles the circuit. The witness is laid out as the constant one, the
+    /// public inputs, the private inputs and then the intermediate variables.
+    pub fn program(&self) -> Result<Program, Error> {
+        let zeros = |len| vec![Scalar::zero(); len];
+        self.synthesize(&zeros(self.public.len()), &zeros(self.private.len()))
+            .map(|(program, _)| program)
+    }
+
+    /// Computes the full witness of `program()` from the inputs, given in
+    /// declaration order.
+    pub fn witness(&self, public: &[Scalar], private: &[Scalar]) -> Result<Vec<Scalar>, Error> {
+        if public.len() != self.public.len() {
+            return Err(Error::BadPublicInputLength {
+                expected: self.public.len(),
+                found: public.len(),
+            });
+        }
+        if private.len() != self.private.len() {
+            return Err(Error::WitnessSizeMismatch {
+                expected: self.private.len(),
+                found: private.len(),
+            });
+        }
+        self.synthesize(public, private).map(|(_, witness)| witness)
+    }
+
+    fn input_name(&self, input: usize) -> String {
+        match self.inputs[input] {
+            Variable::Public(i) => self.public[i].clone(),
+            Variable::Private(i) => self.private[i].clone(),
+            Variable::One => "1".into(),
+        }
+    }
+
+    fn synthesize(
+        &self,
+        public: &[Scalar],
+        private: &[Scalar],
+    ) -> Result<(Program, Vec<Scalar>), Error> {
+        let mut s = Synthesis {
+            cs: ConstraintSystem::new(),
+            intermediates: 0,
+        };
+        let public: Vec<Variable> = public.iter().map(|v| s.cs.alloc_public(*v)).collect();
+        let private: Vec<Variable> = private.iter().map(|v| s.cs.alloc_private(*v)).collect();
+        let inputs: Vec<Variable> = self
+            .inputs
+            .iter()
+            .map(|input| match input {
+                Variable::Public(i) => public[*i],
+                Variable::Private(i) => private[*i],
+                Variable::One => Variable::One,
+            })
+            .collect();
+        let names: Vec<String> = (0..inputs.len()).map(|i| self.input_name(i)).collect();
+        for (var, name) in inputs.iter().zip(&names) {
+            s.cs.label(*var, name);
+        }
+
+        for (lhs, rhs) in &self.assertions {
+            let label = format!(
+                "{} == {}",
+                format_expr(lhs, &names, 0),
+                format_expr(rhs, &names, 0)
+            );
+            let lhs = Value::compile(lhs, &inputs, &mut s);
+            let rhs = Value::compile(rhs, &inputs, &mut s);
+            let diff = lhs.add(rhs.scale(-Scalar::one()), &mut s);
+            match diff.product {
+                Some((a, b)) => s.cs.enforce_labeled(a, b, -diff.linear, label),
+                None => s.cs.enforce_labeled(
+                    diff.linear,
+                    Variable::One,
+                    LinearCombination::default(),
+                    label,
+                ),
+            }
+        }
+        s.cs.finish()
+    }
+}
+
+struct Synthesis {
+    cs: ConstraintSystem,
+    /// Intermediate variables allocated so far, named `v1`, `v2`, ...
+    intermediates: usize,
+}
+
+/// Writes an expression back out, with parentheses only where the
+/// precedence of the operators needs them.
+fn format_expr(expr: &Expr, names: &[String], parent: u8) -> String {
+    let (res, precedence) = match expr {
+        Expr::Constant(c) => (BigUint::from_bytes_le(&c.to_bytes()).to_string(), 5),
+        Expr::Input(i) => (names[*i].clone(), 5),
+        Expr::Add(a, b) => (
+            format!(
+                "{} + {}",
+                format_expr(a, names, 1),
+                format_expr(b, names, 1)
+            ),
+            1,
+        ),
+        Expr::Sub(a, b) => (
+            format!(
+                "{} - {}",
+                format_expr(a, names, 1),
+                format_expr(b, names, 2)
+            ),
+            1,
+        ),
+        Expr::Mul(a, b) => (
+            format!("{}*{}", format_expr(a, names, 2), format_expr(b, names, 2)),
+            2,
+        ),
+        Expr::Neg(a) => (format!("-{}", format_expr(a, names, 3)), 3),
+        Expr::Pow(a, exp) => (format!("{}^{}", format_expr(a, names, 5), exp), 4),
+    };
+    if precedence < parent {
+        format!("({})", res)
+    } else {
+        res
+    }
+}
+
+/// An expression compiled down to at most one pending product plus a linear
+/// part, `a * b + c`. Keeping the last product pending lets an assertion
+/// turn it into the constraint itself instead of an intermediate variable.
+#[derive(Clone, Default)]
+struct Value {
+    product: Option<(LinearCombination, LinearCombination)>,
+    linear: LinearCombination,
+}
+
+impl Value {
+    fn compile(expr: &Expr, inputs: &[Variable], s: &mut Synthesis) -> Value {
+        match expr {
+            Expr::Constant(c) => LinearCombination::from(*c).into(),
+            Expr::Input(i) => LinearCombination::from(inputs[*i]).into(),
+            Expr::Add(a, b) => {
+                let a = Value::compile(a, inputs, s);
+                let b = Value::compile(b, inputs, s);
+                a.add(b, s)
+            }
+            Expr::Sub(a, b) => {
+                let a = Value::compile(a, inputs, s);
+                let b = Value::compile(b, inputs, s);
+                a.add(b.scale(-Scalar::one()), s)
+            }
+            Expr::Mul(a, b) => {
+                let a = Value::compile(a, inputs, s);
+                let b = Value::compile(b, inputs, s);
+                a.mul(b, s)
+            }
+            Expr::Neg(a) => Value::compile(a, inputs, s).scale(-Scalar::one()),
+            Expr::Pow(a, exp) => {
+                let base = Value::compile(a, inputs, s).linearize(s);
+                Value::pow(base, *exp, s)
+            }
+        }
+    }
+
+    /// Square and multiply, leaving the last product pending.
+    fn pow(base: LinearCombination, exp: u64, s: &mut Synthesis) -> Value {
+        match exp {
+            0 => LinearCombination::from(Scalar::one()).into(),
+            1 => base.into(),
+            _ => {
+                let half: Value = Value::pow(base.clone(), exp / 2, s).linearize(s).into();
+                let square = half.clone().mul(half, s);
+                if exp & 1 == 0 {
+                    square
+                } else {
+                    Value::from(square.linearize(s)).mul(base.into(), s)
+                }
+            }
+        }
+    }
+
+    fn constant(&self) -> Option<Scalar> {
+        if self.product.is_some() || self.linear.0.iter().any(|(v, _)| *v != Variable::One) {
+            return None;
+        }
+        Some(self.linear.0.iter().map(|(_, c)| c).sum())
+    }
+
+    fn scale(self, by: Scalar) -> Value {
+        Value {
+            product: self.product.map(|(a, b)| (a * by, b)),
+            linear: self.linear * by,
+        }
+    }
+
+    fn add(self, other: Value, s: &mut Synthesis) -> Value {
+        let other = match self.product {
+            Some(_) if other.product.is_some() => other.linearize(s).into(),
+            _ => other,
+        };
+        Value {
+            product: self.product.or(other.product),
+            linear: self.linear + other.linear,
+        }
+    }
+
+    fn mul(self, other: Value, s: &mut Synthesis) -> Value {
+        if let Some(c) = self.constant() {
+            return other.scale(c);
+        }
+        if let Some(c) = other.constant() {
+            return self.scale(c);
+        }
+        Value {
+            product: Some((self.linearize(s), other.linearize(s))),
+            linear: LinearCombination::default(),
+        }
+    }
+
+    /// Replaces the pending product with a new intermediate variable.
+    fn linearize(self, s: &mut Synthesis) -> LinearCombination {
+        match self.product {
+            Some((a, b)) => {
+                let value = s.cs.value(a.clone()) * s.cs.value(b.clone());
+                let t = s.cs.alloc_private(value);
+                s.intermediates += 1;
+                let name = format!("v{}", s.intermediates);
+                let label = format!(
+                    "{} = {}",
+                    name,
+                    format_product(&s.cs.describe(&a), &s.cs.describe(&b))
+                );
+                s.cs.label(t, name);
+                s.cs.enforce_labeled(a, b, t, label);
+                self.linear + t
+            }
+            None => self.linear,
+        }
+    }
+}
+
+impl From<LinearCombination> for Value {
+    fn from(linear: LinearCombination) -> Self {
+        Value {
+            product: None,
+            linear,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(String),
+    Symbol(&'static str),
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    names: HashMap<String, usize>,
+    circuit: Circuit,
+}
+
+const SYMBOLS: [&str; 9] = ["==", "+", "-", "*", "^", "(", ")", ",", ";"];
+
+impl Parser {
+    fn new(source: &str) -> Result<Self, ParseError> {
+        let mut tokens = vec![];
+        for (index, line) in source.lines().enumerate() {
+            let line_number = index + 1;
+            let line = line.split("//").next().unwrap();
+            let mut rest = line.trim_start();
+            while !rest.is_empty() {
+                let token = if let Some(symbol) = SYMBOLS.iter().find(|s| rest.starts_with(**s)) {
+                    Token::Symbol(symbol)
+                } else {
+                    let len = rest
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                        .unwrap_or(rest.len());
+                    let word = &rest[..len];
+                    match word.chars().next() {
+                        Some(c) if c.is_ascii_digit() => {
+                            if !word.chars().all(|c| c.is_ascii_digit()) {
+                                return Err(ParseError {
+                                    line: line_number,
+                                    message: format!("invalid token '{}'", word),
+                                });
+                            }
+                            Token::Number(word.into())
+                        }
+                        Some(c) if c.is_ascii_alphabetic() || c == '_' => Token::Ident(word.into()),
+                        _ => {
+                            return Err(ParseError {
+                                line: line_number,
+                                message: format!(
+                                    "unexpected character '{}'",
+                                    rest.chars().next().unwrap()
+                                ),
+                            })
+                        }
+                    }
+                };
+                let len = match &token {
+                    Token::Symbol(s) => s.len(),
+                    Token::Ident(s) | Token::Number(s) => s.len(),
+                };
+                tokens.push((token, line_number));
+                rest = rest[len..].trim_start();
+            }
+        }
+
+        Ok(Parser {
+            tokens,
+            pos: 0,
+            names: HashMap::new(),
+            circuit: Circuit {
+                public: vec![],
+                private: vec![],
+                inputs: vec![],
+                assertions: vec![],
+            },
+        })
+    }
+
+    fn circuit(mut self) -> Result<Circuit, ParseError> {
+        while self.pos < self.tokens.len() {
+            match self.peek() {
+                Some(Token::Ident(word)) if word == "public" || word == "private" => {
+                    let public = word == "public";
+                    self.pos += 1;
+                    self.declaration(public)?;
+                }
+                _ => {
+                    let lhs = self.expr()?;
+                    self.expect("==")?;
+                    let rhs = self.expr()?;
+                    self.circuit.assertions.push((lhs, rhs));
+                }
+            }
+            self.expect(";")?;
+        }
+        if self.circuit.assertions.is_empty() {
+            return Err(self.error("expected at least one assertion".into()));
+        }
+        Ok(self.circuit)
+    }
+
+    fn declaration(&mut self, public: bool) -> Result<(), ParseError> {
+        loop {
+            let name = match self.next() {
+                Some(Token::Ident(name)) => name,
+                _ => return Err(self.error("expected an input name".into())),
+            };
+            if name == "public" || name == "private" || self.names.contains_key(&name) {
+                return Err(self.error(format!("'{}' is already declared", name)));
+            }
+            let (names, variable): (&mut Vec<String>, fn(usize) -> Variable) = if public {
+                (&mut self.circuit.public, Variable::Public)
+            } else {
+                (&mut self.circuit.private, Variable::Private)
+            };
+            self.circuit.inputs.push(variable(names.len()));
+            names.push(name.clone());
+            self.names.insert(name, self.circuit.inputs.len() - 1);
+
+            if self.peek() != Some(&Token::Symbol(",")) {
+                return Ok(());
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn expr(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.term()?;
+        loop {
+            expr = match self.peek() {
+                Some(Token::Symbol("+")) => {
+                    self.pos += 1;
+                    Expr::Add(Box::new(expr), Box::new(self.term()?))
+                }
+                Some(Token::Symbol("-")) => {
+                    self.pos += 1;
+                    Expr::Sub(Box::new(expr), Box::new(self.term()?))
+                }
+                _ => return Ok(expr),
+            };
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::Symbol("*")) {
+            self.pos += 1;
+            expr = Expr::Mul(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ParseError> {
+        if self.peek() == Some(&Token::Symbol("-")) {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.atom()?;
+        if self.peek() != Some(&Token::Symbol("^")) {
+            return Ok(base);
+        }
+        self.pos += 1;
+        match self.next() {
+            Some(Token::Number(exp)) => exp
+                .parse()
+                .map(|exp| Expr::Pow(Box::new(base), exp))
+                .map_err(|_| self.error(format!("exponent '{}' is too large", exp))),
+            _ => Err(self.error("expected an integer exponent".into())),
+        }
+    }
+
+    fn atom(&mut self) -> Result<Expr, ParseError> {
+        match self.next() {
+            Some(Token::Number(value)) => from_dec_str(&value)
+                .map(Expr::Constant)
+                .map_err(|_| self.error(format!("constant '{}' is out of range", value))),
+            Some(Token::Ident(name)) => match self.names.get(&name) {
+                Some(input) => Ok(Expr::Input(*input)),
+                None => Err(self.error(format!("'{}' is not declared", name))),
+            },
+            Some(Token::Symbol("(")) => {
+                let expr = self.expr()?;
+                self.expect(")")?;
+                Ok(expr)
+            }
+            _ => Err(self.error("expected an expression".into())),
+        }
+    }
+
+    fn expect(&mut self, symbol: &'static str) -> Result<(), ParseError> {
+        match self.next() {
+            Some(Token::Symbol(s)) if s == symbol => Ok(()),
+            _ => Err(self.error(format!("expected '{}'", symbol))),
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.peek().cloned();
+        self.pos += 1;
+        token
+    }
+
+    /// An error at the last consumed token, or the end of the input.
+    fn error(&self, message: String) -> ParseError {
+        let index = self
+            .pos
+            .saturating_sub(1)
+            .min(self.tokens.len().saturating_sub(1));
+        ParseError {
+            line: self.tokens.get(index).map_or(1, |(_, line)| *line),
+            message,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field::from_i64, prover::Prover, setup::TrustedSetup, verifier::Verifier};
+
+    fn prove(circuit: &Circuit, public: &[i64], private: &[i64]) -> Result<bool, Error> {
+        let public = public.iter().map(|v| from_i64(*v)).collect::<Vec<_>>();
+        let private = private.iter().map(|v| from_i64(*v)).collect::<Vec<_>>();
+        let program = circuit.program()?;
+        let witness = circuit.witness(&public, &private)?;
+        let inputs = program.public_inputs(&witness)?;
+        let (pk, vk) = TrustedSetup::new(&program);
+        let proof = Prover::new(program, pk)?.prove(&witness)?;
+        Verifier::new(vk).verify(&proof, &inputs)
+    }
+
+    #[test]
+    fn dsl_matches_hand_written_constraints() {
+        let circuit = Circuit::parse(
+            "
+            // y^2 = 4x^3 + 2z + 9
+            public y;
+            private x, z;
+            y^2 == 4*x^3 + 2*z + 9;
+            ",
+        )
+        .unwrap();
+        assert_eq!(circuit.public, ["y"]);
+        assert_eq!(circuit.private, ["x", "z"]);
+
+        // x * x = v1, -4v1 * x = v2, y * y = 2z + 9 - v2
+        let program = circuit.program().unwrap();
+        assert_eq!(
+            (program.left.len(), program.cols, program.public.len()),
+            (3, 6, 2)
+        );
+        assert_eq!(
+            circuit.witness(&[from_i64(5)], &[from_i64(1), from_i64(6)]),
+            Ok([1, 5, 1, 6, 1, -4].map(from_i64).to_vec())
+        );
+
+        assert_eq!(prove(&circuit, &[5], &[1, 6]), Ok(true));
+        assert_eq!(prove(&circuit, &[7], &[1, 18]), Ok(true));
+        assert!(matches!(
+            prove(&circuit, &[7], &[1, 19]),
+            Err(Error::BadWitness { rows }) if rows.len() == 1 && rows[0].row == 2
+        ));
+    }
+
+    #[test]
+    fn dsl_labels() {
+        let circuit = Circuit::parse("public y; private x, z; y^2 == 4*x^3 + 2*z + 9;").unwrap();
+        let program = circuit.program().unwrap();
+        let names: Vec<_> = (0..program.cols).map(|c| program.column_name(c)).collect();
+        assert_eq!(names, ["w0", "y", "x", "z", "v1", "v2"]);
+        assert_eq!(program.constraint_name(0), "v1 = x*x");
+        assert_eq!(program.constraint_name(1), "v2 = -4*v1*x");
+        assert_eq!(program.constraint_name(2), "y^2 == 4*x^3 + 2*z + 9");
+
+        let witness = circuit.witness(&[from_i64(7)], &[from_i64(1), from_i64(19)]);
+        let err = witness.and_then(|w| program.check_witness(&w)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Bad witness: constraint `y^2 == 4*x^3 + 2*z + 9` failed"
+        );
+    }
+
+    #[test]
+    fn dsl_expressions() {
+        let circuit = Circuit::parse(
+            "public out; private x, y, z;
+            529 == x^3 + 4*x^2 - y*z + 4;
+            out == -(x - 2) * (3 + y) + z^0 * 7;
+            x^5 == out * 0 + 3125;",
+        )
+        .unwrap();
+        // 125 + 100 - (-15 * 20) + 4 = 529, -(5 - 2) * (3 - 15) + 7 = 43
+        assert_eq!(prove(&circuit, &[43], &[5, -15, 20]), Ok(true));
+        assert!(matches!(
+            prove(&circuit, &[44], &[5, -15, 20]),
+            Err(Error::BadWitness { rows }) if rows.len() == 1
+        ));
+
+        assert_eq!(
+            circuit.witness(&[], &[]),
+            Err(Error::BadPublicInputLength {
+                expected: 1,
+                found: 0
+            })
+        );
+    }
+
+    #[test]
+    fn dsl_parse_errors() {
+        let error = |source: &str| Circuit::parse(source).unwrap_err();
+        assert_eq!(
+            error("public x;\nx * y == 1;"),
+            ParseError {
+                line: 2,
+                message: "'y' is not declared".into()
+            }
+        );
+        assert_eq!(
+            error("public x; private x;").message,
+            "'x' is already declared"
+        );
+        assert_eq!(error("public x; x == 1").message, "expected ';'");
+        assert_eq!(
+            error("public x; x = 1;").message,
+            "unexpected character '='"
+        );
+        assert_eq!(error("public x; 2x == 1;").message, "invalid token '2x'");
+        assert_eq!(
+            error("public x; x ^ x == 1;").message,
+            "expected an integer exponent"
+        );
+        assert_eq!(
+            error("public x;").message,
+            "expected at least one assertion"
+        );
+        assert_eq!(
+            Error::from(error("public x; (x == 1;")).to_string(),
+            "Parsing failed: line 1: expected ')'"
+        );
+    }
+}
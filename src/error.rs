@@ -0,0 +1,166 @@
+use crate::{dsl::ParseError, field::FieldError, program::UnsatisfiedRow, serialize::DecodeError};
+use bls12_381::Scalar;
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The L, R and O matrices are empty or do not have matching shapes.
+    LroMismatch,
+    /// The public columns, counting the constant one, are none or more than
+    /// the columns.
+    InvalidPublicCount {
+        public: usize,
+        cols: usize,
+    },
+    /// The witness does not have one value per program column.
+    WitnessSizeMismatch {
+        expected: usize,
+        found: usize,
+    },
+    /// The witness does not satisfy the listed constraints.
+    BadWitness {
+        rows: Vec<UnsatisfiedRow>,
+    },
+    /// The first witness column, the constant one, holds another value.
+    ConstantNotOne {
+        found: Scalar,
+    },
+    /// A public column is out of range, repeated or the constant one.
+    InvalidPublicColumn {
+        column: usize,
+    },
+    /// A column pair that cannot be linked when composing programs: out of
+    /// range, a constant column, or a second-program column linked twice.
+    InvalidLink {
+        first: usize,
+        second: usize,
+    },
+    /// The format only supports public columns right after the constant one.
+    PublicNotPrefix,
+    /// The number of public inputs does not match the verifying key.
+    BadPublicInputLength {
+        expected: usize,
+        found: usize,
+    },
+    /// The witness columns that could not be derived from the known ones.
+    UnsolvedWitness {
+        columns: Vec<usize>,
+    },
+    /// The powers of tau cover fewer rows than the program needs.
+    PowersTooSmall {
+        required: usize,
+        available: usize,
+    },
+    /// An accumulator size below two or not a power of two.
+    InvalidAccumulatorSize {
+        size: usize,
+    },
+    /// More rows than the largest evaluation domain, `2^32`, can hold.
+    DomainTooLarge {
+        size: usize,
+    },
+    /// More values than the evaluation domain has elements.
+    DomainTooSmall {
+        size: usize,
+        found: usize,
+    },
+    /// A verifying key without the base point for the constant one.
+    EmptyVerifyingKey,
+    /// A length or column index does not fit the `u32` fields of the binary
+    /// encoding.
+    TooLargeToEncode,
+    /// The keys were not generated for the given program.
+    KeyMismatch,
+    Field(FieldError),
+    Decode(DecodeError),
+    Parse(ParseError),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::LroMismatch => write!(f, "LRO mismatch"),
+            Error::InvalidPublicCount { public, cols } => {
+                write!(f, "Invalid public count {} for {} columns", public, cols)
+            }
+            Error::WitnessSizeMismatch { expected, found } => write!(
+                f,
+                "Witness size mismatch: expected {}, found {}",
+                expected, found
+            ),
+            Error::BadWitness { rows } => {
+                let failures: Vec<String> = rows
+                    .iter()
+                    .map(|row| match &row.label {
+                        Some(label) => format!("constraint `{}` failed", label),
+                        None => format!("constraint {} failed", row.row),
+                    })
+                    .collect();
+                write!(f, "Bad witness: {}", failures.join(", "))
+            }
+            Error::ConstantNotOne { found } => {
+                write!(f, "Witness constant is {:?}, expected one", found)
+            }
+            Error::InvalidPublicColumn { column } => {
+                write!(f, "Invalid public column {}", column)
+            }
+            Error::InvalidLink { first, second } => {
+                write!(f, "Cannot link column {} to column {}", first, second)
+            }
+            Error::PublicNotPrefix => write!(f, "Public columns must come first"),
+            Error::BadPublicInputLength { expected, found } => write!(
+                f,
+                "Bad public input length: expected {}, found {}",
+                expected, found
+            ),
+            Error::UnsolvedWitness { columns } => {
+                write!(f, "Could not solve witness columns {:?}", columns)
+            }
+            Error::PowersTooSmall {
+                required,
+                available,
+            } => write!(
+                f,
+                "Powers of tau too small: {} rows required, {} available",
+                required, available
+            ),
+            Error::DomainTooLarge { size } => {
+                write!(f, "No evaluation domain holds {} rows", size)
+            }
+            Error::DomainTooSmall { size, found } => write!(
+                f,
+                "Domain too small: {} elements for {} values",
+                size, found
+            ),
+            Error::InvalidAccumulatorSize { size } => {
+                write!(f, "Invalid accumulator size {}", size)
+            }
+            Error::EmptyVerifyingKey => write!(f, "Verifying key has no base points"),
+            Error::TooLargeToEncode => write!(f, "Too large to encode"),
+            Error::KeyMismatch => write!(f, "Keys do not match the program"),
+            Error::Field(e) => write!(f, "Bad field element: {}", e),
+            Error::Decode(e) => write!(f, "Decoding failed: {}", e),
+            Error::Parse(e) => write!(f, "Parsing failed: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<FieldError> for Error {
+    fn from(e: FieldError) -> Self {
+        Error::Field(e)
+    }
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Self {
+        Error::Decode(e)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(e: ParseError) -> Self {
+        Error::Parse(e)
+    }
+}
@@ -0,0 +1,162 @@
+use bls12_381::Scalar;
+use std::fmt;
+
+/// Reasons a value could not be converted into a scalar field element.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldError {
+    Empty,
+    InvalidDigit,
+    OutOfRange,
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::Empty => write!(f, "Empty string"),
+            FieldError::InvalidDigit => write!(f, "Invalid digit"),
+            FieldError::OutOfRange => write!(f, "Value not below the field modulus"),
+        }
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+/// Converts a signed integer, mapping negative values to `p - |value|`.
+pub fn from_i64(value: i64) -> Scalar {
+    let abs = Scalar::from(value.unsigned_abs());
+    if value < 0 {
+        -abs
+    } else {
+        abs
+    }
+}
+
+/// Converts an unsigned 128-bit integer. Every such value is below the field
+/// modulus, so the conversion cannot fail.
+pub fn from_u128(value: u128) -> Scalar {
+    Scalar::from_raw([value as u64, (value >> 64) as u64, 0, 0])
+}
+
+/// Parses a base-10 string such as `"52435875175126190479447740508185965837690552500527637822603658699938581184512"`.
+pub fn from_dec_str(value: &str) -> Result<Scalar, FieldError> {
+    parse_radix(value, 10)
+}
+
+/// Parses a base-16 string, with or without a `0x` prefix.
+pub fn from_hex_str(value: &str) -> Result<Scalar, FieldError> {
+    let value = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    parse_radix(value, 16)
+}
+
+/// Converts a big-endian byte string. Leading zero bytes are allowed beyond
+/// 32 bytes, but the value itself must be below the field modulus.
+pub fn from_be_bytes(bytes: &[u8]) -> Result<Scalar, FieldError> {
+    let start = bytes.len().saturating_sub(32);
+    if bytes[..start].iter().any(|b| *b != 0) {
+        return Err(FieldError::OutOfRange);
+    }
+
+    let mut le = [0u8; 32];
+    bytes[start..]
+        .iter()
+        .rev()
+        .enumerate()
+        .for_each(|(i, b)| le[i] = *b);
+    Option::from(Scalar::from_bytes(&le)).ok_or(FieldError::OutOfRange)
+}
+
+fn parse_radix(value: &str, radix: u32) -> Result<Scalar, FieldError> {
+    if value.is_empty() {
+        return Err(FieldError::Empty);
+    }
+
+    // Accumulate into a 256-bit big-endian integer so that values above the
+    // modulus are rejected instead of silently reduced
+    let mut acc = [0u8; 32];
+    for c in value.chars() {
+        let digit = c.to_digit(radix).ok_or(FieldError::InvalidDigit)?;
+        let mut carry = digit;
+        for byte in acc.iter_mut().rev() {
+            let res = *byte as u32 * radix + carry;
+            *byte = res as u8;
+            carry = res >> 8;
+        }
+        if carry != 0 {
+            return Err(FieldError::OutOfRange);
+        }
+    }
+    from_be_bytes(&acc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODULUS_DEC: &str =
+        "52435875175126190479447740508185965837690552500527637822603658699938581184513";
+    const MODULUS_HEX: &str = "0x73eda753299d7d483339d80809a1d80553bda402fffe5bfeffffffff00000001";
+
+    #[test]
+    fn field_from_i64() {
+        assert_eq!(from_i64(5), Scalar::from(5));
+        assert_eq!(from_i64(-5), -Scalar::from(5));
+        assert_eq!(from_i64(i64::MIN), -Scalar::from(1 << 63));
+    }
+
+    #[test]
+    fn field_from_u128() {
+        assert_eq!(from_u128(7), Scalar::from(7));
+        assert_eq!(
+            from_u128(u128::MAX) + Scalar::one(),
+            Scalar::from(1 << 32).square().square()
+        );
+    }
+
+    #[test]
+    fn field_from_dec_str() {
+        assert_eq!(from_dec_str("0"), Ok(Scalar::zero()));
+        assert_eq!(from_dec_str("1234567890"), Ok(Scalar::from(1234567890)));
+        assert_eq!(
+            from_dec_str(&MODULUS_DEC.replace("513", "512")),
+            Ok(-Scalar::one())
+        );
+        assert_eq!(from_dec_str(MODULUS_DEC), Err(FieldError::OutOfRange));
+        assert_eq!(from_dec_str(&"9".repeat(100)), Err(FieldError::OutOfRange));
+        assert_eq!(from_dec_str("12a"), Err(FieldError::InvalidDigit));
+        assert_eq!(from_dec_str("-1"), Err(FieldError::InvalidDigit));
+        assert_eq!(from_dec_str(""), Err(FieldError::Empty));
+        assert_eq!(
+            crate::Error::from(FieldError::InvalidDigit).to_string(),
+            "Bad field element: Invalid digit"
+        );
+    }
+
+    #[test]
+    fn field_from_hex_str() {
+        assert_eq!(from_hex_str("0xff"), Ok(Scalar::from(255)));
+        assert_eq!(from_hex_str("FF"), Ok(Scalar::from(255)));
+        assert_eq!(
+            from_hex_str(&MODULUS_HEX.replace("0001", "0000")),
+            Ok(-Scalar::one())
+        );
+        assert_eq!(from_hex_str(MODULUS_HEX), Err(FieldError::OutOfRange));
+        assert_eq!(from_hex_str("0xg"), Err(FieldError::InvalidDigit));
+        assert_eq!(from_hex_str("0x"), Err(FieldError::Empty));
+    }
+
+    #[test]
+    fn field_from_be_bytes() {
+        assert_eq!(from_be_bytes(&[1, 0]), Ok(Scalar::from(256)));
+        assert_eq!(from_be_bytes(&[]), Ok(Scalar::zero()));
+
+        let mut bytes = [0u8; 40];
+        bytes[39] = 9;
+        assert_eq!(from_be_bytes(&bytes), Ok(Scalar::from(9)));
+        bytes[0] = 1;
+        assert_eq!(from_be_bytes(&bytes), Err(FieldError::OutOfRange));
+        assert_eq!(from_be_bytes(&[0xff; 32]), Err(FieldError::OutOfRange));
+    }
+}
@@ -0,0 +1,360 @@
+//! JSON encoding of verifying keys, proofs and public inputs on the bls12381
+//! curve, using the field names of snarkjs's `verification_key.json`,
+//! `proof.json` and `public.json`. Coordinates are decimal strings and points
+//! are written in projective form with `z = 1`. The encoding has not been
+//! checked against files produced by snarkjs itself.
+
+use crate::{
+    field::from_dec_str, prover::Proof, serialize::DecodeError, setup::VerifyingKey, Error,
+};
+use bls12_381::{pairing, G1Affine, G2Affine, Gt, Scalar};
+use num_bigint::BigUint;
+use serde::{Deserialize, Serialize};
+
+const PROTOCOL: &str = "groth16";
+const CURVE: &str = "bls12381";
+
+type G1Json = [String; 3];
+type G2Json = [[String; 2]; 3];
+type GtJson = [[[String; 2]; 3]; 2];
+
+/// `vk_alphabeta_12` is optional when reading, but must equal
+/// `e(vk_alpha_1, vk_beta_2)` when present.
+#[derive(Serialize, Deserialize)]
+struct VerifyingKeyJson {
+    protocol: String,
+    curve: String,
+    #[serde(rename = "nPublic")]
+    n_public: usize,
+    vk_alpha_1: G1Json,
+    vk_beta_2: G2Json,
+    vk_gamma_2: G2Json,
+    vk_delta_2: G2Json,
+    #[serde(default)]
+    vk_alphabeta_12: Option<GtJson>,
+    #[serde(rename = "IC")]
+    ic: Vec<G1Json>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ProofJson {
+    pi_a: G1Json,
+    pi_b: G2Json,
+    pi_c: G1Json,
+    protocol: String,
+    curve: String,
+}
+
+impl VerifyingKey {
+    /// Fails when the key has no base point for the constant one.
+    pub fn to_json(&self) -> Result<String, Error> {
+        let n_public = self
+            .psi_verifier
+            .len()
+            .checked_sub(1)
+            .ok_or(Error::EmptyVerifyingKey)?;
+        Ok(to_json(&VerifyingKeyJson {
+            protocol: PROTOCOL.into(),
+            curve: CURVE.into(),
+            n_public,
+            vk_alpha_1: g1_to_json(&self.alpha),
+            vk_beta_2: g2_to_json(&self.beta_2),
+            vk_gamma_2: g2_to_json(&self.gamma),
+            vk_delta_2: g2_to_json(&self.delta_2),
+            vk_alphabeta_12: Some(gt_to_json(&pairing(&self.alpha, &self.beta_2))),
+            ic: self.psi_verifier.iter().map(g1_to_json).collect(),
+        }))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, DecodeError> {
+        let key: VerifyingKeyJson = from_json(json)?;
+        check_protocol(&key.protocol, &key.curve)?;
+        if key.ic.len() != key.n_public + 1 {
+            return Err(DecodeError::InvalidJson("IC does not match nPublic".into()));
+        }
+        let vk = VerifyingKey {
+            alpha: g1_from_json(&key.vk_alpha_1)?,
+            beta_2: g2_from_json(&key.vk_beta_2)?,
+            gamma: g2_from_json(&key.vk_gamma_2)?,
+            delta_2: g2_from_json(&key.vk_delta_2)?,
+            psi_verifier: key.ic.iter().map(g1_from_json).collect::<Result<_, _>>()?,
+        };
+        if let Some(alphabeta) = key.vk_alphabeta_12 {
+            if alphabeta != gt_to_json(&pairing(&vk.alpha, &vk.beta_2)) {
+                return Err(DecodeError::InvalidJson(
+                    "vk_alphabeta_12 does not match e(vk_alpha_1, vk_beta_2)".into(),
+                ));
+            }
+        }
+        Ok(vk)
+    }
+}
+
+impl Proof {
+    pub fn to_json(&self) -> String {
+        to_json(&ProofJson {
+            pi_a: g1_to_json(&self.lg1),
+            pi_b: g2_to_json(&self.rg2),
+            pi_c: g1_to_json(&self.og1),
+            protocol: PROTOCOL.into(),
+            curve: CURVE.into(),
+        })
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, DecodeError> {
+        let proof: ProofJson = from_json(json)?;
+        check_protocol(&proof.protocol, &proof.curve)?;
+        Ok(Proof {
+            lg1: g1_from_json(&proof.pi_a)?,
+            rg2: g2_from_json(&proof.pi_b)?,
+            og1: g1_from_json(&proof.pi_c)?,
+        })
+    }
+}
+
+/// Writes public inputs as `public.json` does, without the constant one.
+pub fn public_inputs_to_json(inputs: &[Scalar]) -> String {
+    to_json(&inputs.iter().map(scalar_to_json).collect::<Vec<_>>())
+}
+
+/// Reads the public inputs of a `public.json` file, ready for
+/// `Verifier::verify`.
+pub fn public_inputs_from_json(json: &str) -> Result<Vec<Scalar>, DecodeError> {
+    let inputs: Vec<String> = from_json(json)?;
+    inputs
+        .iter()
+        .map(|input| from_dec_str(input).map_err(|_| DecodeError::InvalidScalar))
+        .collect()
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    serde_json::to_string_pretty(value).expect("JSON values always serialize")
+}
+
+fn from_json<'a, T: Deserialize<'a>>(json: &'a str) -> Result<T, DecodeError> {
+    serde_json::from_str(json).map_err(|e| DecodeError::InvalidJson(e.to_string()))
+}
+
+fn check_protocol(protocol: &str, curve: &str) -> Result<(), DecodeError> {
+    if protocol != PROTOCOL {
+        return Err(DecodeError::UnsupportedProtocol);
+    }
+    if curve != CURVE {
+        return Err(DecodeError::WrongCurve);
+    }
+    Ok(())
+}
+
+fn scalar_to_json(value: &Scalar) -> String {
+    BigUint::from_bytes_le(&value.to_bytes()).to_string()
+}
+
+/// Base field elements in big-endian form, with the bls12_381 encoding flags
+/// in the top bits of the first byte cleared.
+fn fq_to_json(bytes: &[u8]) -> String {
+    let mut bytes = bytes.to_vec();
+    bytes[0] &= 0x1f;
+    BigUint::from_bytes_be(&bytes).to_string()
+}
+
+fn fq_from_json(value: &str) -> Result<[u8; 48], DecodeError> {
+    let value = value
+        .parse::<BigUint>()
+        .map_err(|_| DecodeError::InvalidPoint)?;
+    let bytes = value.to_bytes_be();
+    if bytes.len() > 48 {
+        return Err(DecodeError::InvalidPoint);
+    }
+    let mut res = [0u8; 48];
+    res[48 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(res)
+}
+
+fn g1_to_json(point: &G1Affine) -> G1Json {
+    if bool::from(point.is_identity()) {
+        return ["0", "1", "0"].map(String::from);
+    }
+    let bytes = point.to_uncompressed();
+    [
+        fq_to_json(&bytes[..48]),
+        fq_to_json(&bytes[48..]),
+        "1".into(),
+    ]
+}
+
+fn g1_from_json(point: &G1Json) -> Result<G1Affine, DecodeError> {
+    match point[2].as_str() {
+        "0" => return Ok(G1Affine::identity()),
+        "1" => {}
+        _ => return Err(DecodeError::InvalidPoint),
+    }
+    let mut bytes = [0u8; 96];
+    bytes[..48].copy_from_slice(&fq_from_json(&point[0])?);
+    bytes[48..].copy_from_slice(&fq_from_json(&point[1])?);
+    Option::from(G1Affine::from_uncompressed(&bytes)).ok_or(DecodeError::InvalidPoint)
+}
+
+/// Each coordinate is `[c0, c1]`, where the bls12_381 crate writes `c1 || c0`.
+fn g2_to_json(point: &G2Affine) -> G2Json {
+    let pair = |c0: &str, c1: &str| [c0.to_string(), c1.to_string()];
+    if bool::from(point.is_identity()) {
+        return [pair("0", "0"), pair("1", "0"), pair("0", "0")];
+    }
+    let bytes = point.to_uncompressed();
+    [
+        [fq_to_json(&bytes[48..96]), fq_to_json(&bytes[..48])],
+        [fq_to_json(&bytes[144..]), fq_to_json(&bytes[96..144])],
+        pair("1", "0"),
+    ]
+}
+
+/// `Gt` keeps its coordinates private, so they are taken from its `Debug`
+/// output, which lists the twelve base field elements in hex in the order
+/// `c0.c0.c0, c0.c0.c1, c0.c1.c0, ..., c1.c2.c1` that the JSON nests them in.
+fn gt_to_json(value: &Gt) -> GtJson {
+    let debug = format!("{:?}", value);
+    let mut coordinates = debug.split("0x").skip(1).map(|hex| {
+        let digits: String = hex.chars().take_while(char::is_ascii_hexdigit).collect();
+        BigUint::parse_bytes(digits.as_bytes(), 16)
+            .expect("Gt debug output lists hex coordinates")
+            .to_string()
+    });
+    [(); 2].map(|_| {
+        [(); 3].map(|_| {
+            [(); 2].map(|_| {
+                coordinates
+                    .next()
+                    .expect("Gt debug output lists twelve coordinates")
+            })
+        })
+    })
+}
+
+fn g2_from_json(point: &G2Json) -> Result<G2Affine, DecodeError> {
+    match (point[2][0].as_str(), point[2][1].as_str()) {
+        ("0", "0") => return Ok(G2Affine::identity()),
+        ("1", "0") => {}
+        _ => return Err(DecodeError::InvalidPoint),
+    }
+    let mut bytes = [0u8; 192];
+    bytes[..48].copy_from_slice(&fq_from_json(&point[0][1])?);
+    bytes[48..96].copy_from_slice(&fq_from_json(&point[0][0])?);
+    bytes[96..144].copy_from_slice(&fq_from_json(&point[1][1])?);
+    bytes[144..].copy_from_slice(&fq_from_json(&point[1][0])?);
+    Option::from(G2Affine::from_uncompressed(&bytes)).ok_or(DecodeError::InvalidPoint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        field::from_i64, program::Program, prover::Prover, setup::TrustedSetup, verifier::Verifier,
+    };
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    /// A proof for one public input, 33, against keys from known toxic
+    /// waste, encoded outside this crate. Not snarkjs output: its gamma is
+    /// not the G2 generator that snarkjs uses
+    const VERIFICATION_KEY: &str = include_str!("../fixtures/json/verification_key.json");
+    const PROOF: &str = include_str!("../fixtures/json/proof.json");
+    const PUBLIC: &str = include_str!("../fixtures/json/public.json");
+
+    #[test]
+    fn json_fixture() {
+        let verifier = Verifier::new(VerifyingKey::from_json(VERIFICATION_KEY).unwrap());
+        let proof = Proof::from_json(PROOF).unwrap();
+        let public = public_inputs_from_json(PUBLIC).unwrap();
+        assert_eq!(public, vec![from_i64(33)]);
+
+        assert_eq!(verifier.verify(&proof, &public), Ok(true));
+        assert_eq!(verifier.verify(&proof, &[from_i64(34)]), Ok(false));
+    }
+
+    #[test]
+    fn json_round_trip() {
+        // x * x = y, x * y = z
+        let program = Program::new_sparse(
+            &[vec![(1, Scalar::one())], vec![(1, Scalar::one())]],
+            &[vec![(1, Scalar::one())], vec![(2, Scalar::one())]],
+            &[vec![(2, Scalar::one())], vec![(3, Scalar::one())]],
+            4,
+            2,
+        )
+        .unwrap();
+        let (pk, vk) = TrustedSetup::new_with_rng(&program, &mut ChaCha20Rng::seed_from_u64(1));
+        let witness = [1, 3, 9, 27].map(from_i64);
+        let proof = Prover::new(program, pk).unwrap().prove(&witness).unwrap();
+
+        let json = vk.to_json().unwrap();
+        assert!(json.contains("vk_alphabeta_12"));
+        let vk = VerifyingKey::from_json(&json).unwrap();
+        let proof = Proof::from_json(&proof.to_json()).unwrap();
+        let public = public_inputs_from_json(&public_inputs_to_json(&witness[1..2])).unwrap();
+        assert_eq!(Verifier::new(vk).verify(&proof, &public), Ok(true));
+
+        let identity = Proof {
+            lg1: G1Affine::identity(),
+            rg2: G2Affine::identity(),
+            og1: G1Affine::generator(),
+        };
+        assert_eq!(Proof::from_json(&identity.to_json()), Ok(identity));
+    }
+
+    #[test]
+    fn json_gt_coordinates() {
+        let identity = gt_to_json(&Gt::identity());
+        assert_eq!(identity[0][0], ["1", "0"].map(String::from));
+        assert!(identity
+            .iter()
+            .flatten()
+            .flatten()
+            .skip(1)
+            .all(|c| c == "0"));
+    }
+
+    #[test]
+    fn json_errors() {
+        assert_eq!(
+            Proof::from_json(&PROOF.replace("bls12381", "bn128")),
+            Err(DecodeError::WrongCurve)
+        );
+        assert_eq!(
+            VerifyingKey::from_json(&VERIFICATION_KEY.replace("groth16", "plonk")),
+            Err(DecodeError::UnsupportedProtocol)
+        );
+        assert!(matches!(
+            Proof::from_json("{}"),
+            Err(DecodeError::InvalidJson(_))
+        ));
+        assert!(matches!(
+            VerifyingKey::from_json(&VERIFICATION_KEY.replace("\"nPublic\": 1", "\"nPublic\": 2")),
+            Err(DecodeError::InvalidJson(_))
+        ));
+
+        // vk_alphabeta_12 must be the pairing of alpha and beta
+        let vk = VerifyingKey::from_json(VERIFICATION_KEY).unwrap();
+        let mut key: serde_json::Value = serde_json::from_str(&vk.to_json().unwrap()).unwrap();
+        key["vk_alphabeta_12"][1][2][0] = "1".into();
+        assert!(matches!(
+            VerifyingKey::from_json(&key.to_string()),
+            Err(DecodeError::InvalidJson(_))
+        ));
+        let empty = VerifyingKey {
+            psi_verifier: vec![],
+            ..vk
+        };
+        assert_eq!(empty.to_json(), Err(Error::EmptyVerifyingKey));
+
+        let mut proof: serde_json::Value = serde_json::from_str(PROOF).unwrap();
+        proof["pi_a"][0] = "1".into();
+        assert_eq!(
+            Proof::from_json(&proof.to_string()),
+            Err(DecodeError::InvalidPoint)
+        );
+        assert_eq!(
+            public_inputs_from_json("[\"x\"]"),
+            Err(DecodeError::InvalidScalar)
+        );
+    }
+}
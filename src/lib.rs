@@ -1,17 +1,55 @@
+pub mod analysis;
+mod binfile;
+pub mod circuit;
+pub mod compose;
+mod domain;
+pub mod dsl;
+mod error;
+pub mod field;
+pub mod json;
+pub mod phase2;
 mod poly;
+pub mod powers_of_tau;
 pub mod program;
 pub mod prover;
+pub mod ptau;
+pub mod r1cs;
+pub mod serialize;
 pub mod setup;
+mod solver;
+pub mod underconstrained;
 mod utils;
 pub mod verifier;
+pub mod wtns;
+pub mod zkey;
+
+pub use error::Error;
 
 #[cfg(test)]
 mod tests {
-    use crate::program::Program;
+    use crate::field::{from_dec_str, from_i64};
+    use crate::program::{Program, SparseRow, UnsatisfiedRow};
     use crate::prover::Prover;
     use crate::setup::TrustedSetup;
     use crate::verifier::Verifier;
-    use bls12_381::{G1Affine, G1Projective};
+    use crate::Error;
+    use bls12_381::{G1Affine, G1Projective, Scalar};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn to_field(rows: &[Vec<i64>]) -> Vec<Vec<Scalar>> {
+        rows.iter().map(|row| to_witness(row)).collect()
+    }
+
+    fn to_sparse(rows: &[Vec<(usize, i64)>]) -> Vec<SparseRow> {
+        rows.iter()
+            .map(|row| row.iter().map(|(col, v)| (*col, from_i64(*v))).collect())
+            .collect()
+    }
+
+    fn to_witness(values: &[i64]) -> Vec<Scalar> {
+        values.iter().map(|value| from_i64(*value)).collect()
+    }
 
     fn get_prover_and_verifier() -> (Prover, Verifier) {
         // y^2 = 4x^3 + 2z + 9
@@ -30,119 +68,242 @@ mod tests {
         //
         let public = 2;
         let c = Program::new(
-            &[
+            &to_field(&[
                 vec![0, 1, 0, 0, 0, 0],
                 vec![0, 0, 1, 0, 0, 0],
                 vec![0, 0, 0, 0, 0, 1],
-            ],
-            &[
+            ]),
+            &to_field(&[
                 vec![0, 1, 0, 0, 0, 0],
                 vec![0, 0, 1, 0, 0, 0],
                 vec![0, 0, 4, 0, 0, 0],
-            ],
-            &[
+            ]),
+            &to_field(&[
                 vec![0, 0, 0, 0, 1, 0],
                 vec![0, 0, 0, 0, 0, 1],
                 vec![-9, 0, 0, -2, 1, 0],
-            ],
+            ]),
             public,
-        );
-        let s = TrustedSetup::new(&c);
-        let verifier = Verifier::new(s.clone());
-        let prover = Prover::new(c, s);
+        )
+        .unwrap();
+        let (proving_key, verifying_key) = TrustedSetup::new(&c);
+        let verifier = Verifier::new(verifying_key);
+        let prover = Prover::new(c, proving_key).unwrap();
         (prover, verifier)
     }
 
     #[test]
-    #[should_panic(expected = "LRO mismatch")]
     fn fail_lro_mismatch_empty_rows() {
-        Program::new(&[], &[], &[], 0);
+        assert_eq!(
+            Program::new(&[], &[], &[], 1).err(),
+            Some(Error::LroMismatch)
+        );
     }
 
     #[test]
-    #[should_panic(expected = "LRO mismatch")]
     fn fail_lro_mismatch_empty_columns() {
-        Program::new(&[vec![]], &[vec![]], &[vec![]], 0);
+        assert_eq!(
+            Program::new(&[vec![]], &[vec![]], &[vec![]], 1).err(),
+            Some(Error::LroMismatch)
+        );
     }
 
     #[test]
-    #[should_panic(expected = "LRO mismatch")]
     fn fail_lro_mismatch_rows() {
-        Program::new(&[vec![1], vec![2]], &[vec![1]], &[vec![1]], 0);
+        assert_eq!(
+            Program::new(
+                &to_field(&[vec![1], vec![2]]),
+                &to_field(&[vec![1]]),
+                &to_field(&[vec![1]]),
+                1,
+            )
+            .err(),
+            Some(Error::LroMismatch)
+        );
     }
 
     #[test]
-    #[should_panic(expected = "LRO mismatch")]
     fn fail_lro_mismatch_columns() {
-        Program::new(&[vec![1, 2]], &[vec![1]], &[vec![1]], 0);
+        assert_eq!(
+            Program::new(
+                &to_field(&[vec![1, 2]]),
+                &to_field(&[vec![1]]),
+                &to_field(&[vec![1]]),
+                1,
+            )
+            .err(),
+            Some(Error::LroMismatch)
+        );
+    }
+
+    #[test]
+    fn fail_lro_mismatch_sparse_column() {
+        assert_eq!(
+            Program::new_sparse(
+                &to_sparse(&[vec![(2, 1)]]),
+                &to_sparse(&[vec![(0, 1)]]),
+                &to_sparse(&[vec![(1, 1)]]),
+                2,
+                1,
+            )
+            .err(),
+            Some(Error::LroMismatch)
+        );
+    }
+
+    #[test]
+    fn fail_invalid_public_count() {
+        // The constant column is always public, and there are only two columns
+        for public in [0, 3] {
+            assert_eq!(
+                Program::new(
+                    &to_field(&[vec![0, 1]]),
+                    &to_field(&[vec![0, 1]]),
+                    &to_field(&[vec![0, 1]]),
+                    public,
+                )
+                .err(),
+                Some(Error::InvalidPublicCount { public, cols: 2 })
+            );
+            assert_eq!(
+                Program::new_sparse(
+                    &to_sparse(&[vec![(1, 1)]]),
+                    &to_sparse(&[vec![(1, 1)]]),
+                    &to_sparse(&[vec![(1, 1)]]),
+                    2,
+                    public,
+                )
+                .err(),
+                Some(Error::InvalidPublicCount { public, cols: 2 })
+            );
+        }
     }
 
     #[test]
-    #[should_panic(expected = "Witness size mismatch")]
     fn fail_witness_lro_mismatch() {
-        let witness = [1, 5, 1, 6, 25, 1, 2];
+        let witness = to_witness(&[1, 5, 1, 6, 25, 1, 2]);
         let (prover, _) = get_prover_and_verifier();
-        prover.prove(&witness);
+        assert_eq!(
+            prover.prove(&witness),
+            Err(Error::WitnessSizeMismatch {
+                expected: 6,
+                found: 7
+            })
+        );
     }
 
     #[test]
-    #[should_panic(expected = "Bad witness")]
     fn fail_bad_witness() {
-        let witness = [1, 6, 2, 6, 36, 4];
+        let witness = to_witness(&[1, 6, 2, 6, 36, 4]);
         let (prover, _) = get_prover_and_verifier();
-        prover.prove(&witness);
+        // v2 * 4x = 4 * 8, but v1 - 2z - 9 = 15
+        assert_eq!(
+            prover.prove(&witness),
+            Err(Error::BadWitness {
+                rows: vec![UnsatisfiedRow {
+                    row: 2,
+                    label: None,
+                    left: from_i64(4),
+                    right: from_i64(8),
+                    output: from_i64(15)
+                }]
+            })
+        );
     }
 
     #[test]
-    #[should_panic(expected = "Bad public input length")]
     fn fail_public_input_small() {
         let (prover, verifier) = get_prover_and_verifier();
-        let witness = [1, 5, 1, 6, 25, 1];
-        let (mut lg1, rg2, og1) = prover.prove(&witness);
-        lg1 = G1Affine::from(lg1 + G1Projective::generator());
-        verifier.verify(lg1, rg2, og1, &witness[0..1]);
+        let witness = to_witness(&[1, 5, 1, 6, 25, 1]);
+        let mut proof = prover.prove(&witness).unwrap();
+        proof.lg1 = G1Affine::from(proof.lg1 + G1Projective::generator());
+        assert_eq!(
+            verifier.verify(&proof, &[]),
+            Err(Error::BadPublicInputLength {
+                expected: 1,
+                found: 0
+            })
+        );
     }
 
     #[test]
-    #[should_panic(expected = "Bad public input length")]
     fn fail_public_input_big() {
         let (prover, verifier) = get_prover_and_verifier();
-        let witness = [1, 5, 1, 6, 25, 1];
-        let (mut lg1, rg2, og1) = prover.prove(&witness);
-        lg1 = G1Affine::from(lg1 + G1Projective::generator());
-        verifier.verify(lg1, rg2, og1, &witness[0..3]);
+        let witness = to_witness(&[1, 5, 1, 6, 25, 1]);
+        let mut proof = prover.prove(&witness).unwrap();
+        proof.lg1 = G1Affine::from(proof.lg1 + G1Projective::generator());
+        assert_eq!(
+            verifier.verify(&proof, &witness[1..3]),
+            Err(Error::BadPublicInputLength {
+                expected: 1,
+                found: 2
+            })
+        );
     }
 
     #[test]
-    #[should_panic(expected = "Bad proof")]
     fn fail_wrong_public_input() {
         let (prover, verifier) = get_prover_and_verifier();
-        let witness = [1, 5, 1, 6, 25, 1];
-        let (lg1, rg2, og1) = prover.prove(&witness);
-        verifier.verify(lg1, rg2, og1, &witness[1..3]);
+        let witness = to_witness(&[1, 5, 1, 6, 25, 1]);
+        let proof = prover.prove(&witness).unwrap();
+        assert_eq!(verifier.verify(&proof, &witness[2..3]), Ok(false));
+    }
+
+    #[test]
+    fn fail_key_for_other_program() {
+        // x * x = y, and the same followed by y * y = z
+        let one_row = Program::new(
+            &to_field(&[vec![0, 1, 0]]),
+            &to_field(&[vec![0, 1, 0]]),
+            &to_field(&[vec![0, 0, 1]]),
+            1,
+        )
+        .unwrap();
+        let two_rows = Program::new(
+            &to_field(&[vec![0, 1, 0, 0], vec![0, 0, 1, 0]]),
+            &to_field(&[vec![0, 1, 0, 0], vec![0, 0, 1, 0]]),
+            &to_field(&[vec![0, 0, 1, 0], vec![0, 0, 0, 1]]),
+            1,
+        )
+        .unwrap();
+        let (proving_key, _) = TrustedSetup::new(&one_row);
+        assert!(matches!(
+            Prover::new(two_rows, proving_key),
+            Err(Error::KeyMismatch)
+        ));
+    }
+
+    #[test]
+    fn fail_constant_not_one() {
+        let (prover, _) = get_prover_and_verifier();
+        // The constant column is checked before any constraint
+        let witness = to_witness(&[2, 10, 2, 12, 50, 2]);
+        assert_eq!(
+            prover.prove(&witness),
+            Err(Error::ConstantNotOne { found: from_i64(2) })
+        );
     }
 
     #[test]
-    #[should_panic(expected = "Bad proof")]
     fn fail_bad_proof() {
         let (prover, verifier) = get_prover_and_verifier();
-        let witness = [1, 5, 1, 6, 25, 1];
-        let (mut lg1, rg2, og1) = prover.prove(&witness);
-        lg1 = G1Affine::from(lg1 + G1Projective::generator());
-        verifier.verify(lg1, rg2, og1, &witness[0..2]);
+        let witness = to_witness(&[1, 5, 1, 6, 25, 1]);
+        let mut proof = prover.prove(&witness).unwrap();
+        proof.lg1 = G1Affine::from(proof.lg1 + G1Projective::generator());
+        assert_eq!(verifier.verify(&proof, &witness[1..2]), Ok(false));
     }
 
     #[test]
     fn pass_1() {
         let (prover, verifier) = get_prover_and_verifier();
 
-        let witness = [1, 5, 1, 6, 25, 1];
-        let (lg1, rg2, og1) = prover.prove(&witness);
-        verifier.verify(lg1, rg2, og1, &witness[0..2]);
+        let witness = to_witness(&[1, 5, 1, 6, 25, 1]);
+        let proof = prover.prove(&witness).unwrap();
+        assert_eq!(verifier.verify(&proof, &witness[1..2]), Ok(true));
 
-        let witness = [1, 7, 1, 18, 49, 1];
-        let (lg1, rg2, og1) = prover.prove(&witness);
-        verifier.verify(lg1, rg2, og1, &witness[0..2]);
+        let witness = to_witness(&[1, 7, 1, 18, 49, 1]);
+        let proof = prover.prove(&witness).unwrap();
+        assert_eq!(verifier.verify(&proof, &witness[1..2]), Ok(true));
     }
 
     #[test]
@@ -160,61 +321,169 @@ mod tests {
         // Example witness:
         // [1, 7, 7, 2, 49, 343]
         //
-        let public = 0;
+        let public = 1;
         let c = Program::new(
-            &[
+            &to_field(&[
                 vec![0, 0, 1, 0, 0, 0],
                 vec![0, 0, 1, 0, 0, 0],
                 vec![0, -1, 0, 0, 0, 0],
-            ],
-            &[
+            ]),
+            &to_field(&[
                 vec![0, 0, 1, 0, 0, 0],
                 vec![0, 0, 0, 0, 1, 0],
                 vec![0, 0, 0, 1, 0, 0],
-            ],
-            &[
+            ]),
+            &to_field(&[
                 vec![0, 0, 0, 0, 1, 0],
                 vec![0, 0, 0, 0, 0, 1],
                 vec![525, 0, 0, 0, -4, -1],
-            ],
+            ]),
             public,
+        )
+        .unwrap();
+        let (proving_key, verifying_key) = TrustedSetup::new(&c);
+        let verifier = Verifier::new(verifying_key);
+        let prover = Prover::new(c, proving_key).unwrap();
+
+        let witness = to_witness(&[1, 7, 7, 2, 49, 343]);
+        let proof = prover.prove(&witness).unwrap();
+        assert_eq!(verifier.verify(&proof, &[]), Ok(true));
+    }
+
+    #[test]
+    fn pass_seeded_rng() {
+        let c = Program::new(
+            &to_field(&[vec![0, 1, 0]]),
+            &to_field(&[vec![0, 1, 0]]),
+            &to_field(&[vec![0, 0, 1]]),
+            1,
+        )
+        .unwrap();
+        let witness = to_witness(&[1, 3, 9]);
+
+        let (proving_key, verifying_key) =
+            TrustedSetup::new_with_rng(&c, &mut ChaCha20Rng::seed_from_u64(1));
+        let (same_pk, same_vk) = TrustedSetup::new_with_rng(&c, &mut ChaCha20Rng::seed_from_u64(1));
+        let (other_pk, _) = TrustedSetup::new_with_rng(&c, &mut ChaCha20Rng::seed_from_u64(2));
+        assert_eq!(proving_key, same_pk);
+        assert_eq!(verifying_key, same_vk);
+        assert_ne!(proving_key, other_pk);
+
+        let verifier = Verifier::new(verifying_key);
+        let prover = Prover::new(c, proving_key).unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(3);
+        let proof = prover.prove_with_rng(&witness, &mut rng).unwrap();
+        let other = prover.prove_with_rng(&witness, &mut rng).unwrap();
+        assert_ne!(proof, other);
+        assert_eq!(
+            prover.prove_with_rng(&witness, &mut ChaCha20Rng::seed_from_u64(3)),
+            Ok(proof)
         );
-        let s = TrustedSetup::new(&c);
-        let verifier = Verifier::new(s.clone());
-        let prover = Prover::new(c, s);
+        assert_eq!(verifier.verify(&proof, &[]), Ok(true));
+        assert_eq!(verifier.verify(&other, &[]), Ok(true));
+    }
 
-        let witness = [1, 7, 7, 2, 49, 343];
-        let (lg1, rg2, og1) = prover.prove(&witness);
-        verifier.verify(lg1, rg2, og1, &[]);
+    #[test]
+    fn pass_sparse() {
+        // Same circuit as `get_prover_and_verifier`, without the zero entries
+        let public = 2;
+        let c = Program::new_sparse(
+            &to_sparse(&[vec![(1, 1)], vec![(2, 1)], vec![(5, 1)]]),
+            &to_sparse(&[vec![(1, 1)], vec![(2, 1)], vec![(2, 4)]]),
+            &to_sparse(&[vec![(4, 1)], vec![(5, 1)], vec![(0, -9), (3, -2), (4, 1)]]),
+            6,
+            public,
+        )
+        .unwrap();
+        let (proving_key, verifying_key) = TrustedSetup::new(&c);
+        let verifier = Verifier::new(verifying_key);
+        let prover = Prover::new(c, proving_key).unwrap();
+
+        let witness = to_witness(&[1, 7, 1, 18, 49, 1]);
+        let proof = prover.prove(&witness).unwrap();
+        assert_eq!(verifier.verify(&proof, &witness[1..2]), Ok(true));
+    }
+
+    #[test]
+    fn pass_interleaved_public() {
+        // x * x = y, x * y = z with z and then x public
+        //
+        // Witness:
+        // [1, x, y, z]
+        let mut c = Program::new_sparse(
+            &to_sparse(&[vec![(1, 1)], vec![(1, 1)]]),
+            &to_sparse(&[vec![(1, 1)], vec![(2, 1)]]),
+            &to_sparse(&[vec![(2, 1)], vec![(3, 1)]]),
+            4,
+            1,
+        )
+        .unwrap();
+        c.set_public(&[3, 1]).unwrap();
+        let (proving_key, verifying_key) = TrustedSetup::new(&c);
+        let verifier = Verifier::new(verifying_key);
+
+        let witness = to_witness(&[1, 3, 9, 27]);
+        assert_eq!(c.public_inputs(&witness), Ok(to_witness(&[27, 3])));
+        let proof = Prover::new(c, proving_key)
+            .unwrap()
+            .prove(&witness)
+            .unwrap();
+        assert_eq!(verifier.verify(&proof, &to_witness(&[27, 3])), Ok(true));
+        assert_eq!(verifier.verify(&proof, &to_witness(&[3, 27])), Ok(false));
+        assert_eq!(verifier.verify(&proof, &to_witness(&[9, 3])), Ok(false));
+    }
+
+    #[test]
+    fn pass_field_witness() {
+        // x * y = 1, where y is the field inverse of x
+        //
+        // Witness:
+        // [1, x, y]
+        //
+        let c = Program::new(
+            &to_field(&[vec![0, 1, 0]]),
+            &to_field(&[vec![0, 0, 1]]),
+            &to_field(&[vec![1, 0, 0]]),
+            1,
+        )
+        .unwrap();
+        let (proving_key, verifying_key) = TrustedSetup::new(&c);
+        let verifier = Verifier::new(verifying_key);
+        let prover = Prover::new(c, proving_key).unwrap();
+
+        let x = from_dec_str("12345678901234567890123456789").unwrap();
+        let witness = [Scalar::one(), x, x.invert().unwrap()];
+        let proof = prover.prove(&witness).unwrap();
+        assert_eq!(verifier.verify(&proof, &[]), Ok(true));
     }
 
     #[test]
-    #[should_panic(expected = "Bad proof")]
     fn fail_proof_from_other() {
         let (prover, _) = get_prover_and_verifier();
-        let witness = [1, 5, 1, 6, 25, 1];
-        let (lg1, rg2, og1) = prover.prove(&witness);
+        let witness = to_witness(&[1, 5, 1, 6, 25, 1]);
+        let proof = prover.prove(&witness).unwrap();
         let public = 2;
         let c = Program::new(
-            &[
+            &to_field(&[
                 vec![0, 0, 1, 0, 0, 0],
                 vec![0, 0, 1, 0, 0, 0],
                 vec![0, -1, 0, 0, 0, 0],
-            ],
-            &[
+            ]),
+            &to_field(&[
                 vec![0, 0, 1, 0, 0, 0],
                 vec![0, 0, 0, 0, 1, 0],
                 vec![0, 0, 0, 1, 0, 0],
-            ],
-            &[
+            ]),
+            &to_field(&[
                 vec![0, 0, 0, 0, 1, 0],
                 vec![0, 0, 0, 0, 0, 1],
                 vec![525, 0, 0, 0, -4, -1],
-            ],
+            ]),
             public,
-        );
-        let s = TrustedSetup::new(&c);
-        let verifier = Verifier::new(s);
-        verifier.verify(lg1, rg2, og1, &witness[0..2]);
+        )
+        .unwrap();
+        let (_, verifying_key) = TrustedSetup::new(&c);
+        let verifier = Verifier::new(verifying_key);
+        assert_eq!(verifier.verify(&proof, &witness[1..2]), Ok(false));
     }
 }
@@ -1,7 +1,10 @@
+use bls12_381::Scalar;
+use groth16_rs::field::from_i64;
 use groth16_rs::program::Program;
 use groth16_rs::prover::Prover;
 use groth16_rs::setup::TrustedSetup;
 use groth16_rs::verifier::Verifier;
+use groth16_rs::Error;
 
 // y^2 = 4x^3 + 2z + 9
 //
@@ -17,36 +20,46 @@ use groth16_rs::verifier::Verifier;
 // [1, 5, 1, 6, 25, 1]
 // [1, 7, 1, 18, 49, 1]
 //
-fn main() {
+fn main() -> Result<(), Error> {
     let public = 2;
     let program = Program::new(
-        &[
+        &to_field(&[
             vec![0, 1, 0, 0, 0, 0],
             vec![0, 0, 1, 0, 0, 0],
             vec![0, 0, 0, 0, 0, 1],
-        ],
-        &[
+        ]),
+        &to_field(&[
             vec![0, 1, 0, 0, 0, 0],
             vec![0, 0, 1, 0, 0, 0],
             vec![0, 0, 4, 0, 0, 0],
-        ],
-        &[
+        ]),
+        &to_field(&[
             vec![0, 0, 0, 0, 1, 0],
             vec![0, 0, 0, 0, 0, 1],
             vec![-9, 0, 0, -2, 1, 0],
-        ],
+        ]),
         public,
-    );
-    let setup = TrustedSetup::new(&program);
+    )?;
+    let (proving_key, verifying_key) = TrustedSetup::new(&program);
 
-    let verifier = Verifier::new(setup.clone());
-    let prover = Prover::new(program, setup);
+    let verifier = Verifier::new(verifying_key);
+    let prover = Prover::new(program, proving_key)?;
 
-    let witness = [1, 5, 1, 6, 25, 1];
-    let (lg1, rg2, og1) = prover.prove(&witness);
-    verifier.verify(lg1, rg2, og1, &witness[0..public]);
+    let witness = to_witness(&[1, 5, 1, 6, 25, 1]);
+    let proof = prover.prove(&witness)?;
+    assert!(verifier.verify(&proof, &witness[1..public])?);
 
-    let witness = [1, 7, 1, 18, 49, 1];
-    let (lg1, rg2, og1) = prover.prove(&witness);
-    verifier.verify(lg1, rg2, og1, &witness[0..public]);
+    let witness = to_witness(&[1, 7, 1, 18, 49, 1]);
+    let proof = prover.prove(&witness)?;
+    assert!(verifier.verify(&proof, &witness[1..public])?);
+
+    Ok(())
+}
+
+fn to_field(rows: &[Vec<i64>]) -> Vec<Vec<Scalar>> {
+    rows.iter().map(|row| to_witness(row)).collect()
+}
+
+fn to_witness(values: &[i64]) -> Vec<Scalar> {
+    values.iter().map(|value| from_i64(*value)).collect()
 }
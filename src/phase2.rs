@@ -0,0 +1,233 @@
+use crate::{
+    powers_of_tau::{same_ratio, Accumulator, KnowledgeProof},
+    program::Program,
+    serialize::Encoding,
+    setup::{keys_from_powers, ProvingKey, VerifyingKey},
+    Error,
+};
+use bls12_381::{G1Affine, G1Projective, G2Affine, Scalar};
+use ff::Field;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+/// One participant's update of delta, `delta_after = delta_before * x`, with
+/// a proof of knowledge of `x`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DeltaContribution {
+    pub delta_after: G1Affine,
+    pub proof: KnowledgeProof,
+}
+
+/// Circuit-specific keys going through a phase-2 ceremony. They start from
+/// a powers-of-tau accumulator with gamma and delta set to one, and every
+/// contribution rescales delta and the points divided by it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Phase2 {
+    pub proving_key: ProvingKey,
+    pub verifying_key: VerifyingKey,
+    pub contributions: Vec<DeltaContribution>,
+    /// Hash of the initial keys, which every contribution is bound to.
+    pub circuit_digest: [u8; 64],
+}
+
+impl Phase2 {
+    pub fn new(c: &Program, powers: &Accumulator) -> Result<Self, Error> {
+        let (proving_key, verifying_key) =
+            keys_from_powers(c, powers, Scalar::one(), Scalar::one())?;
+        let circuit_digest = digest(&proving_key, &verifying_key)?;
+        Ok(Phase2 {
+            proving_key,
+            verifying_key,
+            contributions: vec![],
+            circuit_digest,
+        })
+    }
+
+    pub fn contribute(&self) -> Phase2 {
+        self.contribute_with_rng(&mut OsRng)
+    }
+
+    pub fn contribute_with_rng(&self, rng: &mut (impl RngCore + CryptoRng)) -> Phase2 {
+        let x = Scalar::random(&mut *rng);
+        let x_inv = x.invert().unwrap();
+        let context = self.context(&self.proving_key.delta_1);
+
+        let mut next = self.clone();
+        let key = &mut next.proving_key;
+        key.delta_1 = G1Affine::from(key.delta_1 * x);
+        key.delta_2 = G2Affine::from(key.delta_2 * x);
+        key.t_tau_g1 = scale(&key.t_tau_g1, x_inv);
+        key.psi_prover = scale(&key.psi_prover, x_inv);
+        next.verifying_key.delta_2 = key.delta_2;
+        next.contributions.push(DeltaContribution {
+            delta_after: key.delta_1,
+            proof: KnowledgeProof::new(x, &context, rng),
+        });
+        next
+    }
+
+    /// Checks that the keys were derived from `powers` and `c`, and that
+    /// delta is the product of the secrets behind every contribution.
+    pub fn verify(&self, c: &Program, powers: &Accumulator) -> Result<bool, Error> {
+        let initial = Phase2::new(c, powers)?;
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let (pk, vk) = (&self.proving_key, &self.verifying_key);
+        let (pk0, vk0) = (&initial.proving_key, &initial.verifying_key);
+
+        // Everything independent of delta must match the initial keys exactly
+        if self.circuit_digest != initial.circuit_digest
+            || (pk.alpha, pk.beta_1, pk.beta_2) != (pk0.alpha, pk0.beta_1, pk0.beta_2)
+            || (&pk.left_g1, &pk.right_g1, &pk.right_g2)
+                != (&pk0.left_g1, &pk0.right_g1, &pk0.right_g2)
+            || (vk.alpha, vk.beta_2, vk.gamma) != (vk0.alpha, vk0.beta_2, vk0.gamma)
+            || vk.psi_verifier != vk0.psi_verifier
+            || vk.delta_2 != pk.delta_2
+            || pk.t_tau_g1.len() != pk0.t_tau_g1.len()
+            || pk.psi_prover.len() != pk0.psi_prover.len()
+        {
+            return Ok(false);
+        }
+
+        let mut delta = g1;
+        for contribution in &self.contributions {
+            if !contribution.proof.verify(&self.context(&delta))
+                || !same_ratio(
+                    (delta, contribution.delta_after),
+                    (g2, contribution.proof.g2),
+                )
+            {
+                return Ok(false);
+            }
+            delta = contribution.delta_after;
+        }
+        if pk.delta_1 != delta || !same_ratio((g1, pk.delta_1), (g2, pk.delta_2)) {
+            return Ok(false);
+        }
+
+        // Every point divided by delta: e(final, delta) == e(initial, 1)
+        let (initial_sum, final_sum) = random_combination(
+            pk0.t_tau_g1.iter().chain(pk0.psi_prover.iter()),
+            pk.t_tau_g1.iter().chain(pk.psi_prover.iter()),
+        );
+        Ok(same_ratio((final_sum, initial_sum), (g2, pk.delta_2)))
+    }
+
+    fn context(&self, delta: &G1Affine) -> Vec<u8> {
+        [&self.circuit_digest[..], &delta.to_compressed()].concat()
+    }
+}
+
+fn digest(proving_key: &ProvingKey, verifying_key: &VerifyingKey) -> Result<[u8; 64], Error> {
+    let mut hasher = Sha512::new();
+    hasher.update(proving_key.to_bytes(Encoding::Compressed)?);
+    hasher.update(verifying_key.to_bytes(Encoding::Compressed)?);
+    Ok(hasher.finalize().into())
+}
+
+fn scale(points: &[G1Affine], by: Scalar) -> Vec<G1Affine> {
+    points.iter().map(|p| G1Affine::from(p * by)).collect()
+}
+
+fn random_combination<'a>(
+    a: impl Iterator<Item = &'a G1Affine>,
+    b: impl Iterator<Item = &'a G1Affine>,
+) -> (G1Affine, G1Affine) {
+    let mut sum_a = G1Projective::identity();
+    let mut sum_b = G1Projective::identity();
+    a.zip(b).for_each(|(a, b)| {
+        let r = Scalar::random(OsRng);
+        sum_a += a * r;
+        sum_b += b * r;
+    });
+    (sum_a.into(), sum_b.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field::from_i64, prover::Prover, verifier::Verifier};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn program() -> Program {
+        // x * x = y, x * y = z
+        Program::new_sparse(
+            &[vec![(1, Scalar::one())], vec![(1, Scalar::one())]],
+            &[vec![(1, Scalar::one())], vec![(2, Scalar::one())]],
+            &[vec![(2, Scalar::one())], vec![(3, Scalar::one())]],
+            4,
+            2,
+        )
+        .unwrap()
+    }
+
+    fn powers() -> Accumulator {
+        Accumulator::new(2)
+            .unwrap()
+            .contribute_with_rng(&mut ChaCha20Rng::seed_from_u64(1))
+            .0
+    }
+
+    #[test]
+    fn phase2_contributions() {
+        let (program, powers) = (program(), powers());
+        let mut params = Phase2::new(&program, &powers).unwrap();
+        assert_eq!(params.verify(&program, &powers), Ok(true));
+
+        for seed in 0..2 {
+            params = params.contribute_with_rng(&mut ChaCha20Rng::seed_from_u64(seed));
+            assert_eq!(params.verify(&program, &powers), Ok(true));
+        }
+        assert_eq!(params.contributions.len(), 2);
+        assert_ne!(params.proving_key.delta_1, G1Affine::generator());
+
+        let witness = [1, 3, 9, 27].map(from_i64);
+        let proof = Prover::new(program, params.proving_key)
+            .unwrap()
+            .prove(&witness)
+            .unwrap();
+        assert_eq!(
+            Verifier::new(params.verifying_key).verify(&proof, &witness[1..2]),
+            Ok(true)
+        );
+    }
+
+    #[test]
+    fn phase2_bad_contributions() {
+        let (program, powers) = (program(), powers());
+        let params = Phase2::new(&program, &powers)
+            .unwrap()
+            .contribute_with_rng(&mut ChaCha20Rng::seed_from_u64(1));
+
+        // Rescaling delta without a matching contribution
+        let mut forged = params.clone();
+        forged.proving_key.delta_1 = G1Affine::from(forged.proving_key.delta_1 * Scalar::from(2));
+        forged.proving_key.delta_2 = G2Affine::from(forged.proving_key.delta_2 * Scalar::from(2));
+        forged.verifying_key.delta_2 = forged.proving_key.delta_2;
+        assert_eq!(forged.verify(&program, &powers), Ok(false));
+
+        let mut forged = params.clone();
+        forged.proving_key.t_tau_g1[0] = G1Affine::generator();
+        assert_eq!(forged.verify(&program, &powers), Ok(false));
+
+        let mut forged = params.clone();
+        forged.proving_key.left_g1[2] = G1Affine::generator();
+        assert_eq!(forged.verify(&program, &powers), Ok(false));
+
+        let mut forged = params.clone();
+        forged.contributions[0].proof.response += Scalar::one();
+        assert_eq!(forged.verify(&program, &powers), Ok(false));
+
+        // Keys for one circuit do not verify against another
+        let other = Program::new_sparse(
+            &[vec![(1, Scalar::one())], vec![(2, Scalar::one())]],
+            &[vec![(1, Scalar::one())], vec![(1, Scalar::one())]],
+            &[vec![(2, Scalar::one())], vec![(3, Scalar::one())]],
+            4,
+            2,
+        )
+        .unwrap();
+        assert_eq!(params.verify(&other, &powers), Ok(false));
+    }
+}
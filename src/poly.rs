@@ -53,7 +53,7 @@ impl Poly {
         if zeros == len {
             return -1;
         }
-        return max_index.try_into().unwrap();
+        max_index.try_into().unwrap()
     }
 
     pub fn eval(&self, x: Scalar) -> Scalar {
@@ -61,7 +61,7 @@ impl Poly {
         let mut res = Scalar::zero();
         for i in 0..self.0.len() {
             res += self.0[i] * curr;
-            curr = curr * x;
+            curr *= x;
         }
         res
     }
@@ -73,7 +73,7 @@ impl Poly {
 
     pub fn interpolate(domain: Vec<Scalar>, evaluations: Vec<Scalar>) -> Self {
         assert!(domain.len() == evaluations.len());
-        assert!(domain.len() > 0);
+        assert!(!domain.is_empty());
 
         let x = Poly::new(vec![Scalar::zero(), Scalar::one()]);
         let mut acc = Poly::new(vec![]);
@@ -111,10 +111,10 @@ impl Poly {
 
         let mut new_coeffs = vec![Scalar::zero(); size];
         lhs.0.iter().enumerate().for_each(|(index, e)| {
-            new_coeffs[index] = new_coeffs[index] + e;
+            new_coeffs[index] += e;
         });
         rhs.0.iter().enumerate().for_each(|(index, e)| {
-            new_coeffs[index] = new_coeffs[index] + e;
+            new_coeffs[index] += e;
         });
         Poly::new(new_coeffs)
     }
@@ -124,7 +124,7 @@ impl Poly {
     }
 
     pub fn mul(lhs: Poly, rhs: Poly) -> Poly {
-        if lhs.0.len() == 0 || rhs.0.len() == 0 {
+        if lhs.0.is_empty() || rhs.0.is_empty() {
             return Poly::new(vec![]);
         }
         let zero = Scalar::zero();
@@ -133,7 +133,7 @@ impl Poly {
         lhs.0.iter().enumerate().for_each(|(i, e)| {
             if *e != zero {
                 rhs.0.iter().enumerate().for_each(|(j, er)| {
-                    new_coeffs[i + j] = new_coeffs[i + j] + (e * er);
+                    new_coeffs[i + j] += e * er;
                 });
             }
         });
@@ -159,7 +159,7 @@ impl Poly {
             let shift: usize = (remainder.degree() - rhs.degree()).try_into().unwrap();
 
             let mut coeffs = vec![Scalar::zero(); shift];
-            coeffs.push(coefficient.clone());
+            coeffs.push(coefficient);
 
             let subtractee = Poly::mul(Poly::new(coeffs), rhs.clone());
 
@@ -0,0 +1,378 @@
+use crate::Error;
+use bls12_381::{pairing, G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use ff::Field;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+use sha2::{Digest, Sha512};
+
+/// Powers of a shared secret tau, as produced by a phase-1 ceremony. An
+/// accumulator of size `n` serves any program with up to `n` padded rows.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Accumulator {
+    /// `[tau^i]_1` for `i < 2n - 1`, enough for the `t(tau) * tau^i` terms of h(x).
+    pub tau_g1: Vec<G1Affine>,
+    /// `[tau^i]_2` for `i < n`.
+    pub tau_g2: Vec<G2Affine>,
+    /// `[alpha * tau^i]_1` for `i < n`.
+    pub alpha_tau_g1: Vec<G1Affine>,
+    /// `[beta * tau^i]_1` for `i < n`.
+    pub beta_tau_g1: Vec<G1Affine>,
+    pub beta_g2: G2Affine,
+}
+
+/// Schnorr proof of knowledge of `x` for `g1 = [x]_1`, together with `g2 = [x]_2`
+/// so that the same `x` can be checked against the accumulator with pairings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KnowledgeProof {
+    pub g1: G1Affine,
+    pub g2: G2Affine,
+    pub commitment: G1Affine,
+    pub response: Scalar,
+}
+
+/// What a participant publishes next to their updated accumulator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Contribution {
+    pub tau: KnowledgeProof,
+    pub alpha: KnowledgeProof,
+    pub beta: KnowledgeProof,
+}
+
+impl Accumulator {
+    /// The starting point of the ceremony, where every secret is one. The
+    /// size must be a power of two and at least two.
+    pub fn new(size: usize) -> Result<Self, Error> {
+        if size < 2 || !size.is_power_of_two() {
+            return Err(Error::InvalidAccumulatorSize { size });
+        }
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        Ok(Accumulator {
+            tau_g1: vec![g1; 2 * size - 1],
+            tau_g2: vec![g2; size],
+            alpha_tau_g1: vec![g1; size],
+            beta_tau_g1: vec![g1; size],
+            beta_g2: g2,
+        })
+    }
+
+    pub fn size(&self) -> usize {
+        self.tau_g2.len()
+    }
+
+    /// Multiplies tau, alpha and beta by fresh secrets, which are dropped as
+    /// soon as the new accumulator is computed.
+    pub fn contribute(&self) -> (Accumulator, Contribution) {
+        self.contribute_with_rng(&mut OsRng)
+    }
+
+    pub fn contribute_with_rng(
+        &self,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (Accumulator, Contribution) {
+        let tau = Scalar::random(&mut *rng);
+        let alpha = Scalar::random(&mut *rng);
+        let beta = Scalar::random(&mut *rng);
+
+        let digest = self.digest();
+        let contribution = Contribution {
+            tau: KnowledgeProof::new(tau, &digest, rng),
+            alpha: KnowledgeProof::new(alpha, &digest, rng),
+            beta: KnowledgeProof::new(beta, &digest, rng),
+        };
+
+        let mut power = Scalar::one();
+        let powers: Vec<Scalar> = (0..self.tau_g1.len())
+            .map(|_| {
+                let res = power;
+                power *= tau;
+                res
+            })
+            .collect();
+
+        let next = Accumulator {
+            tau_g1: scale_g1(&self.tau_g1, &powers, Scalar::one()),
+            tau_g2: self
+                .tau_g2
+                .iter()
+                .zip(powers.iter())
+                .map(|(p, s)| G2Affine::from(p * s))
+                .collect(),
+            alpha_tau_g1: scale_g1(&self.alpha_tau_g1, &powers, alpha),
+            beta_tau_g1: scale_g1(&self.beta_tau_g1, &powers, beta),
+            beta_g2: G2Affine::from(self.beta_g2 * beta),
+        };
+        (next, contribution)
+    }
+
+    /// Checks that `next` is this accumulator updated by the secrets behind
+    /// `contribution`, and that `next` is itself well formed.
+    pub fn verify_contribution(&self, next: &Accumulator, contribution: &Contribution) -> bool {
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        let digest = self.digest();
+
+        self.has_size(self.size())
+            && next.has_size(self.size())
+            && [contribution.tau, contribution.alpha, contribution.beta]
+                .iter()
+                .all(|proof| proof.verify(&digest))
+            && same_ratio((self.tau_g1[1], next.tau_g1[1]), (g2, contribution.tau.g2))
+            && same_ratio(
+                (self.alpha_tau_g1[0], next.alpha_tau_g1[0]),
+                (g2, contribution.alpha.g2),
+            )
+            && same_ratio(
+                (self.beta_tau_g1[0], next.beta_tau_g1[0]),
+                (g2, contribution.beta.g2),
+            )
+            && same_ratio((g1, next.beta_tau_g1[0]), (g2, next.beta_g2))
+            && next.is_well_formed()
+    }
+
+    /// Decoded accumulators can hold anything, so sizes that `new` rejects
+    /// are not well formed either.
+    pub(crate) fn has_size(&self, size: usize) -> bool {
+        size >= 2
+            && size.is_power_of_two()
+            && self.tau_g1.len() == 2 * size - 1
+            && self.tau_g2.len() == size
+            && self.alpha_tau_g1.len() == size
+            && self.beta_tau_g1.len() == size
+    }
+
+    /// Checks that every vector holds consecutive powers of the same tau,
+    /// using random linear combinations so the cost is a handful of pairings.
+    fn is_well_formed(&self) -> bool {
+        let g1 = G1Affine::generator();
+        let g2 = G2Affine::generator();
+        if self.tau_g1[0] != g1
+            || self.tau_g2[0] != g2
+            || bool::from(self.tau_g1[1].is_identity())
+            || bool::from(self.alpha_tau_g1[0].is_identity())
+            || bool::from(self.beta_tau_g1[0].is_identity())
+        {
+            return false;
+        }
+
+        let tau = (self.tau_g1[0], self.tau_g1[1]);
+        let (a, b) = consecutive_g1(&self.tau_g1);
+        let (a2, b2) = consecutive_g2(&self.tau_g2);
+        let (alpha_a, alpha_b) = consecutive_g1(&self.alpha_tau_g1);
+        let (beta_a, beta_b) = consecutive_g1(&self.beta_tau_g1);
+        let tau_2 = (g2, self.tau_g2[1]);
+
+        same_ratio((a, b), tau_2)
+            && same_ratio(tau, (a2, b2))
+            && same_ratio((alpha_a, alpha_b), tau_2)
+            && same_ratio((beta_a, beta_b), tau_2)
+    }
+
+    fn digest(&self) -> [u8; 64] {
+        let mut hasher = Sha512::new();
+        self.tau_g1
+            .iter()
+            .chain(self.alpha_tau_g1.iter())
+            .chain(self.beta_tau_g1.iter())
+            .for_each(|p| hasher.update(p.to_compressed()));
+        self.tau_g2
+            .iter()
+            .chain(std::iter::once(&self.beta_g2))
+            .for_each(|p| hasher.update(p.to_compressed()));
+        hasher.finalize().into()
+    }
+}
+
+/// Verifies a whole phase-1 transcript, starting from `Accumulator::new(size)`,
+/// and returns the final accumulator if every contribution checks out.
+pub fn verify_transcript(
+    size: usize,
+    transcript: &[(Accumulator, Contribution)],
+) -> Option<Accumulator> {
+    let mut current = Accumulator::new(size).ok()?;
+    for (next, contribution) in transcript {
+        if !current.verify_contribution(next, contribution) {
+            return None;
+        }
+        current = next.clone();
+    }
+    Some(current)
+}
+
+impl KnowledgeProof {
+    /// Proves knowledge of `x`, bound to `context` so the proof cannot be
+    /// replayed against another accumulator.
+    pub(crate) fn new(x: Scalar, context: &[u8], rng: &mut (impl RngCore + CryptoRng)) -> Self {
+        let k = Scalar::random(&mut *rng);
+        let g1 = G1Affine::from(G1Affine::generator() * x);
+        let commitment = G1Affine::from(G1Affine::generator() * k);
+        KnowledgeProof {
+            g1,
+            g2: G2Affine::from(G2Affine::generator() * x),
+            commitment,
+            response: k + challenge(context, &g1, &commitment) * x,
+        }
+    }
+
+    pub(crate) fn verify(&self, context: &[u8]) -> bool {
+        let c = challenge(context, &self.g1, &self.commitment);
+        !bool::from(self.g1.is_identity())
+            && G1Affine::generator() * self.response == self.commitment + self.g1 * c
+            && pairing(&self.g1, &G2Affine::generator())
+                == pairing(&G1Affine::generator(), &self.g2)
+    }
+}
+
+fn challenge(context: &[u8], point: &G1Affine, commitment: &G1Affine) -> Scalar {
+    let mut hasher = Sha512::new();
+    hasher.update(context);
+    hasher.update(point.to_compressed());
+    hasher.update(commitment.to_compressed());
+    Scalar::from_bytes_wide(&hasher.finalize().into())
+}
+
+/// `e(a.0, b.1) == e(a.1, b.0)`, i.e. `a.1 / a.0 == b.1 / b.0` in the exponent.
+pub(crate) fn same_ratio(a: (G1Affine, G1Affine), b: (G2Affine, G2Affine)) -> bool {
+    pairing(&a.0, &b.1) == pairing(&a.1, &b.0)
+}
+
+fn scale_g1(points: &[G1Affine], powers: &[Scalar], mul: Scalar) -> Vec<G1Affine> {
+    points
+        .iter()
+        .zip(powers.iter())
+        .map(|(p, s)| G1Affine::from(p * (s * mul)))
+        .collect()
+}
+
+/// Random combinations `(sum r_i * p_i, sum r_i * p_{i+1})` of consecutive points.
+fn consecutive_g1(points: &[G1Affine]) -> (G1Affine, G1Affine) {
+    let mut a = G1Projective::identity();
+    let mut b = G1Projective::identity();
+    points.windows(2).for_each(|w| {
+        let r = Scalar::random(OsRng);
+        a += w[0] * r;
+        b += w[1] * r;
+    });
+    (a.into(), b.into())
+}
+
+fn consecutive_g2(points: &[G2Affine]) -> (G2Affine, G2Affine) {
+    let mut a = G2Projective::identity();
+    let mut b = G2Projective::identity();
+    points.windows(2).for_each(|w| {
+        let r = Scalar::random(OsRng);
+        a += w[0] * r;
+        b += w[1] * r;
+    });
+    (a.into(), b.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        field::from_i64, program::Program, prover::Prover, setup::TrustedSetup, verifier::Verifier,
+        Error,
+    };
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn transcript(size: usize, contributions: u64) -> Vec<(Accumulator, Contribution)> {
+        let mut current = Accumulator::new(size).unwrap();
+        (0..contributions)
+            .map(|seed| {
+                let (next, contribution) =
+                    current.contribute_with_rng(&mut ChaCha20Rng::seed_from_u64(seed));
+                current = next.clone();
+                (next, contribution)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn powers_of_tau_transcript() {
+        let steps = transcript(4, 3);
+        let last = verify_transcript(4, &steps).unwrap();
+        assert_eq!(last, steps[2].0);
+        assert_eq!(last.size(), 4);
+        assert_eq!(last.tau_g1.len(), 7);
+    }
+
+    #[test]
+    fn powers_of_tau_bad_transcript() {
+        let steps = transcript(4, 2);
+
+        // Skipping a step breaks the link between consecutive accumulators
+        assert_eq!(verify_transcript(4, &steps[1..]), None);
+
+        // A contribution is bound to the accumulator it was made against
+        let mut replayed = steps.clone();
+        replayed[1].1 = steps[0].1;
+        assert_eq!(verify_transcript(4, &replayed), None);
+
+        let mut broken = steps.clone();
+        broken[1].0.tau_g1[3] = G1Affine::generator();
+        assert_eq!(verify_transcript(4, &broken), None);
+
+        let mut broken = steps.clone();
+        broken[1].0.alpha_tau_g1.pop();
+        assert_eq!(verify_transcript(4, &broken), None);
+
+        let mut broken = steps.clone();
+        broken[0].1.tau.response += Scalar::one();
+        assert_eq!(verify_transcript(4, &broken), None);
+
+        // Empty accumulators, e.g. decoded from a malformed file, are rejected
+        let empty = Accumulator {
+            tau_g1: vec![],
+            tau_g2: vec![],
+            alpha_tau_g1: vec![],
+            beta_tau_g1: vec![],
+            beta_g2: G2Affine::generator(),
+        };
+        assert!(!empty.verify_contribution(&empty, &steps[0].1));
+        assert!(!empty.verify_contribution(&steps[0].0, &steps[0].1));
+        assert_eq!(verify_transcript(0, &steps), None);
+        for size in [0, 1, 3, 6] {
+            assert_eq!(
+                Accumulator::new(size),
+                Err(Error::InvalidAccumulatorSize { size })
+            );
+        }
+    }
+
+    #[test]
+    fn powers_of_tau_setup() {
+        // x * x = y, x * y = z
+        let program = Program::new(
+            &[vec![0, 1, 0, 0], vec![0, 1, 0, 0]].map(|r| r.into_iter().map(from_i64).collect()),
+            &[vec![0, 1, 0, 0], vec![0, 0, 1, 0]].map(|r| r.into_iter().map(from_i64).collect()),
+            &[vec![0, 0, 1, 0], vec![0, 0, 0, 1]].map(|r| r.into_iter().map(from_i64).collect()),
+            2,
+        )
+        .unwrap();
+        let powers = verify_transcript(4, &transcript(4, 2)).unwrap();
+        let mut rng = ChaCha20Rng::seed_from_u64(7);
+
+        let (proving_key, verifying_key) =
+            TrustedSetup::from_powers(&program, &powers, &mut rng).unwrap();
+        let witness = [1, 3, 9, 27].map(from_i64);
+        let proof = Prover::new(program, proving_key)
+            .unwrap()
+            .prove(&witness)
+            .unwrap();
+        assert_eq!(
+            Verifier::new(verifying_key).verify(&proof, &witness[1..2]),
+            Ok(true)
+        );
+
+        let large = Program::new_sparse(&vec![vec![]; 5], &vec![vec![]; 5], &vec![vec![]; 5], 1, 1)
+            .unwrap();
+        assert_eq!(
+            TrustedSetup::from_powers(&large, &powers, &mut rng).err(),
+            Some(Error::PowersTooSmall {
+                required: 8,
+                available: 4
+            })
+        );
+    }
+}
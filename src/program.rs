@@ -1,23 +1,55 @@
 use crate::{
+    domain::EvaluationDomain,
     poly::Poly,
-    utils::{to_poly, to_scalar},
+    utils::{eval_rows, to_sparse},
+    Error,
 };
 use bls12_381::Scalar;
+use num_bigint::BigUint;
+use std::fmt;
+
+/// A constraint row stored as `(column, coefficient)` pairs for its non-zero entries.
+pub type SparseRow = Vec<(usize, Scalar)>;
+
+/// A constraint a witness does not satisfy, with the values it gives to each side.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UnsatisfiedRow {
+    pub row: usize,
+    pub label: Option<String>,
+    /// `<L_i, w>`
+    pub left: Scalar,
+    /// `<R_i, w>`
+    pub right: Scalar,
+    /// `<O_i, w>`
+    pub output: Scalar,
+}
 
 pub struct Program {
     pub rows: usize,
     pub cols: usize,
-    pub left: Vec<Poly>,
-    pub right: Vec<Poly>,
-    pub output: Vec<Poly>,
+    pub left: Vec<SparseRow>,
+    pub right: Vec<SparseRow>,
+    pub output: Vec<SparseRow>,
     pub t: Poly,
-    pub public: usize,
+    /// The public columns in the order the verifier takes them, starting
+    /// with the constant one in column 0.
+    pub public: Vec<usize>,
+    pub domain: EvaluationDomain,
+    /// Optional names for the witness columns, such as `x` or `v1`.
+    pub column_labels: Vec<Option<String>>,
+    /// Optional labels for the constraints, such as `v1 = y*y`.
+    pub constraint_labels: Vec<Option<String>>,
 }
 
 impl Program {
-    pub fn new(left: &[Vec<i64>], right: &[Vec<i64>], output: &[Vec<i64>], public: usize) -> Self {
-        if left.len() == 0
-            || left[0].len() == 0
+    pub fn new(
+        left: &[Vec<Scalar>],
+        right: &[Vec<Scalar>],
+        output: &[Vec<Scalar>],
+        public: usize,
+    ) -> Result<Self, Error> {
+        if left.is_empty()
+            || left[0].is_empty()
             || left.len() != right.len()
             || right.len() != output.len()
             || left
@@ -26,32 +58,369 @@ impl Program {
                 .enumerate()
                 .any(|(index, (l, r))| l.len() != r.len() || r.len() != output[index].len())
         {
-            panic!("LRO mismatch");
+            return Err(Error::LroMismatch);
         }
+        check_public_count(public, left[0].len())?;
 
-        let rows = left.len();
-        let cols = left[0].len();
+        Program::from_sparse(
+            to_sparse(left),
+            to_sparse(right),
+            to_sparse(output),
+            left[0].len(),
+            public,
+        )
+    }
 
-        let (left, right, output) =
-            to_poly(&to_scalar(left), &to_scalar(right), &to_scalar(output));
+    /// Builds a program from constraints given as `(column, coefficient)` pairs,
+    /// omitting zero entries. Every row must only reference columns below `cols`.
+    pub fn new_sparse(
+        left: &[SparseRow],
+        right: &[SparseRow],
+        output: &[SparseRow],
+        cols: usize,
+        public: usize,
+    ) -> Result<Self, Error> {
+        if left.is_empty()
+            || cols == 0
+            || left.len() != right.len()
+            || right.len() != output.len()
+            || left
+                .iter()
+                .chain(right.iter())
+                .chain(output.iter())
+                .any(|row| row.iter().any(|(col, _)| *col >= cols))
+        {
+            return Err(Error::LroMismatch);
+        }
+        check_public_count(public, cols)?;
 
-        // Create t(x) = (x-1)(x-2)(x-3)...(x-n)
-        let mut t = Poly::new(vec![Scalar::one()]);
-        for i in 0..rows {
-            t = Poly::mul(
-                t,
-                Poly::new(vec![-Scalar::from(i as u64 + 1), Scalar::one()]),
-            );
+        Program::from_sparse(left.to_vec(), right.to_vec(), output.to_vec(), cols, public)
+    }
+
+    /// Declares which columns are public, replacing the first `public` ones
+    /// given to the constructor. The verifier takes them in this order, and
+    /// the constant one in column 0 stays public in front of them.
+    pub fn set_public(&mut self, columns: &[usize]) -> Result<(), Error> {
+        let mut seen = vec![false; self.cols];
+        seen[0] = true;
+        for col in columns {
+            if *col >= self.cols || seen[*col] {
+                return Err(Error::InvalidPublicColumn { column: *col });
+            }
+            seen[*col] = true;
+        }
+        self.public = [&[0], columns].concat();
+        Ok(())
+    }
+
+    /// The columns that are not public, in increasing order.
+    pub fn private_columns(&self) -> Vec<usize> {
+        let mut public = vec![false; self.cols];
+        self.public.iter().for_each(|col| public[*col] = true);
+        (0..self.cols).filter(|col| !public[*col]).collect()
+    }
+
+    /// Whether the public columns come first, as in `[1, public..., private...]`.
+    pub fn has_public_prefix(&self) -> bool {
+        self.public.iter().enumerate().all(|(i, col)| i == *col)
+    }
+
+    /// The public inputs a verifier is given: the public columns of the
+    /// witness after the constant one, in declaration order.
+    pub fn public_inputs(&self, witness: &[Scalar]) -> Result<Vec<Scalar>, Error> {
+        if witness.len() != self.cols {
+            return Err(Error::WitnessSizeMismatch {
+                expected: self.cols,
+                found: witness.len(),
+            });
+        }
+        Ok(self.public[1..].iter().map(|col| witness[*col]).collect())
+    }
+
+    /// Checks the witness against every constraint, reporting all the rows
+    /// where `<L_i, w> * <R_i, w> != <O_i, w>`. The first column must hold the
+    /// constant one.
+    pub fn check_witness(&self, witness: &[Scalar]) -> Result<(), Error> {
+        if witness.len() != self.cols {
+            return Err(Error::WitnessSizeMismatch {
+                expected: self.cols,
+                found: witness.len(),
+            });
+        }
+        if witness[0] != Scalar::one() {
+            return Err(Error::ConstantNotOne { found: witness[0] });
+        }
+
+        let size = self.left.len();
+        let left = eval_rows(&self.left, witness, size);
+        let right = eval_rows(&self.right, witness, size);
+        let output = eval_rows(&self.output, witness, size);
+        let rows: Vec<UnsatisfiedRow> = (0..size)
+            .filter(|i| left[*i] * right[*i] != output[*i])
+            .map(|row| UnsatisfiedRow {
+                row,
+                label: self.constraint_labels[row].clone(),
+                left: left[row],
+                right: right[row],
+                output: output[row],
+            })
+            .collect();
+        if !rows.is_empty() {
+            return Err(Error::BadWitness { rows });
+        }
+        Ok(())
+    }
+
+    pub fn label_column(&mut self, col: usize, label: impl Into<String>) {
+        self.column_labels[col] = Some(label.into());
+    }
+
+    pub fn label_constraint(&mut self, row: usize, label: impl Into<String>) {
+        self.constraint_labels[row] = Some(label.into());
+    }
+
+    /// The label of a column, or `w<col>` for unlabelled ones.
+    pub fn column_name(&self, col: usize) -> String {
+        match &self.column_labels[col] {
+            Some(label) => label.clone(),
+            None => format!("w{}", col),
+        }
+    }
+
+    /// Writes out a constraint as `L * R = O` in terms of the column names.
+    pub fn describe_constraint(&self, row: usize) -> String {
+        let side = |row: &SparseRow| {
+            format_terms(row.iter().map(|(col, c)| {
+                let name = (*col != 0).then(|| self.column_name(*col));
+                (name, *c)
+            }))
+        };
+        format!(
+            "{} = {}",
+            format_product(&side(&self.left[row]), &side(&self.right[row])),
+            side(&self.output[row])
+        )
+    }
+
+    /// The constraint's label, or its description when it has none.
+    pub fn constraint_name(&self, row: usize) -> String {
+        match &self.constraint_labels[row] {
+            Some(label) => label.clone(),
+            None => self.describe_constraint(row),
         }
+    }
+
+    fn from_sparse(
+        left: Vec<SparseRow>,
+        right: Vec<SparseRow>,
+        output: Vec<SparseRow>,
+        cols: usize,
+        public: usize,
+    ) -> Result<Self, Error> {
+        // Constraints are interpolated over the roots of unity, so the row
+        // count is padded with empty constraints to a power of two
+        let domain = EvaluationDomain::new(left.len())?;
+        let rows = domain.size;
+        let constraint_labels = vec![None; left.len()];
+
+        // Create t(x) = x^n - 1, which vanishes on every root of unity in the domain
+        let t = domain.vanishing_poly();
 
-        Program {
+        Ok(Program {
             rows,
             cols,
             left,
             right,
             output,
             t,
-            public,
+            public: (0..public).collect(),
+            domain,
+            column_labels: vec![None; cols],
+            constraint_labels,
+        })
+    }
+}
+
+/// The constant one in column 0 is always public, and at most every column is.
+fn check_public_count(public: usize, cols: usize) -> Result<(), Error> {
+    if public == 0 || public > cols {
+        return Err(Error::InvalidPublicCount { public, cols });
+    }
+    Ok(())
+}
+
+/// One line per constraint, with its label when it has one.
+impl fmt::Display for Program {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for row in 0..self.left.len() {
+            match &self.constraint_labels[row] {
+                Some(label) => writeln!(f, "{}: {}", label, self.describe_constraint(row))?,
+                None => writeln!(f, "{}: {}", row, self.describe_constraint(row))?,
+            }
         }
+        Ok(())
+    }
+}
+
+/// Writes `c_1*name_1 + c_2*name_2 + ...`, with `None` naming the constant
+/// term. Coefficients close to the modulus are written as negative numbers.
+pub(crate) fn format_terms(terms: impl Iterator<Item = (Option<String>, Scalar)>) -> String {
+    let mut res = String::new();
+    for (name, coeff) in terms {
+        let (negative, magnitude) = format_coeff(coeff);
+        res += match (res.is_empty(), negative) {
+            (true, false) => "",
+            (true, true) => "-",
+            (false, false) => " + ",
+            (false, true) => " - ",
+        };
+        res += &match (name, magnitude.as_str()) {
+            (Some(name), "1") => name,
+            (Some(name), _) => format!("{}*{}", magnitude, name),
+            (None, _) => magnitude,
+        };
+    }
+    if res.is_empty() {
+        res.push('0');
+    }
+    res
+}
+
+/// Writes `a*b`, wrapping sums in parentheses.
+pub(crate) fn format_product(a: &str, b: &str) -> String {
+    let wrap = |s: &str| {
+        if s.contains(' ') {
+            format!("({})", s)
+        } else {
+            s.to_string()
+        }
+    };
+    format!("{}*{}", wrap(a), wrap(b))
+}
+
+fn format_coeff(coeff: Scalar) -> (bool, String) {
+    let value = BigUint::from_bytes_le(&coeff.to_bytes());
+    let negated = BigUint::from_bytes_le(&(-coeff).to_bytes());
+    if negated < value {
+        (true, negated.to_string())
+    } else {
+        (false, value.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::field::from_i64;
+
+    #[test]
+    fn display_uses_labels() {
+        // x * (x + 2) = 3y - 1
+        let one = Scalar::one();
+        let mut program = Program::new_sparse(
+            &[vec![(1, one)]],
+            &[vec![(1, one), (0, from_i64(2))]],
+            &[vec![(2, from_i64(3)), (0, -one)]],
+            3,
+            1,
+        )
+        .unwrap();
+        assert_eq!(program.to_string(), "0: w1*(w1 + 2) = 3*w2 - 1\n");
+
+        program.label_column(1, "x");
+        program.label_column(2, "y");
+        program.label_constraint(0, "square");
+        assert_eq!(program.to_string(), "square: x*(x + 2) = 3*y - 1\n");
+    }
+
+    #[test]
+    fn set_public_columns() {
+        let one = Scalar::one();
+        let mut program = Program::new_sparse(
+            &[vec![(1, one)]],
+            &[vec![(1, one)]],
+            &[vec![(2, one)]],
+            4,
+            3,
+        )
+        .unwrap();
+        assert_eq!(program.public, [0, 1, 2]);
+        assert!(program.has_public_prefix());
+
+        program.set_public(&[3, 1]).unwrap();
+        assert_eq!(program.public, [0, 3, 1]);
+        assert_eq!(program.private_columns(), [2]);
+        assert!(!program.has_public_prefix());
+        assert_eq!(
+            program.public_inputs(&[1, 2, 3, 4].map(from_i64)),
+            Ok([4, 2].map(from_i64).to_vec())
+        );
+        assert_eq!(
+            program.public_inputs(&[1, 2, 3].map(from_i64)),
+            Err(Error::WitnessSizeMismatch {
+                expected: 4,
+                found: 3
+            })
+        );
+
+        for column in [0, 4, 3] {
+            assert_eq!(
+                program.set_public(&[3, column]),
+                Err(Error::InvalidPublicColumn { column })
+            );
+        }
+        assert_eq!(program.public, [0, 3, 1]);
+    }
+
+    #[test]
+    fn check_witness_reports_rows() {
+        // x * x = y, x * y = z
+        let one = Scalar::one();
+        let program = Program::new_sparse(
+            &[vec![(1, one)], vec![(1, one)]],
+            &[vec![(1, one)], vec![(2, one)]],
+            &[vec![(2, one)], vec![(3, one)]],
+            4,
+            2,
+        )
+        .unwrap();
+        assert_eq!(program.check_witness(&[1, 3, 9, 27].map(from_i64)), Ok(()));
+
+        assert_eq!(
+            program.check_witness(&[1, 3, 10, 27].map(from_i64)),
+            Err(Error::BadWitness {
+                rows: vec![
+                    UnsatisfiedRow {
+                        row: 0,
+                        label: None,
+                        left: from_i64(3),
+                        right: from_i64(3),
+                        output: from_i64(10)
+                    },
+                    UnsatisfiedRow {
+                        row: 1,
+                        label: None,
+                        left: from_i64(3),
+                        right: from_i64(10),
+                        output: from_i64(27)
+                    }
+                ]
+            })
+        );
+
+        // An all-zero witness satisfies x * x = y but not the constant column
+        assert_eq!(
+            program.check_witness(&[Scalar::zero(); 4]),
+            Err(Error::ConstantNotOne {
+                found: Scalar::zero()
+            })
+        );
+        assert_eq!(
+            program.check_witness(&[one; 3]),
+            Err(Error::WitnessSizeMismatch {
+                expected: 4,
+                found: 3
+            })
+        );
     }
 }
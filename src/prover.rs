@@ -1,80 +1,96 @@
 use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
-use rand::RngCore;
+use ff::Field;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
 
-use crate::{poly::Poly, program::Program, setup::TrustedSetup, utils::witness_to_scalar};
+use crate::{program::Program, setup::ProvingKey, utils::eval_rows, Error};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Proof {
+    pub lg1: G1Affine,
+    pub rg2: G2Affine,
+    pub og1: G1Affine,
+}
 
 pub struct Prover {
-    setup: TrustedSetup,
+    key: ProvingKey,
     program: Program,
 }
 
 impl Prover {
-    pub fn new(program: Program, setup: TrustedSetup) -> Self {
-        Prover { program, setup }
-    }
-
-    pub fn prove(&self, witness: &[i64]) -> (G1Affine, G2Affine, G1Affine) {
-        if witness.len() != self.program.cols {
-            panic!("Witness size mismatch");
+    /// Pairs a program with its proving key, checking that the key has a
+    /// point for every column and row the prover will index.
+    pub fn new(program: Program, key: ProvingKey) -> Result<Self, Error> {
+        if key.left_g1.len() != program.cols
+            || key.right_g1.len() != program.cols
+            || key.right_g2.len() != program.cols
+            || key.psi_prover.len() != program.private_columns().len()
+            || key.t_tau_g1.len() + 1 != program.rows
+        {
+            return Err(Error::KeyMismatch);
         }
-        let witness = witness_to_scalar(witness);
+        Ok(Prover { program, key })
+    }
 
-        let program = &self.program;
-        let setup = &self.setup;
+    pub fn prove(&self, witness: &[Scalar]) -> Result<Proof, Error> {
+        self.prove_with_rng(witness, &mut OsRng)
+    }
 
-        let mut left = vec![Scalar::zero(); program.rows];
-        let mut right = vec![Scalar::zero(); program.rows];
-        let mut output = vec![Scalar::zero(); program.rows];
-        let mut og1 = G1Projective::identity();
+    /// Like `prove`, drawing the blinding factors r and s from the given generator.
+    pub fn prove_with_rng(
+        &self,
+        witness: &[Scalar],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<Proof, Error> {
+        // Rejects bad witnesses with the failing rows before any FFT or group work
+        self.program.check_witness(witness)?;
 
-        witness.iter().enumerate().for_each(|(i, val)| {
-            program.left[i].0.iter().enumerate().for_each(|(j, p)| {
-                left[j] += p * val;
-            });
-            program.right[i].0.iter().enumerate().for_each(|(j, p)| {
-                right[j] += p * val;
-            });
-            program.output[i].0.iter().enumerate().for_each(|(j, p)| {
-                output[j] += p * val;
-            });
+        let program = &self.program;
+        let key = &self.key;
 
-            if i >= program.public {
-                og1 += setup.psi_prover[i - program.public] * val;
-            }
-        });
+        // Evaluations of L(x), R(x) and O(x) on the domain are the per-constraint values
+        let left = eval_rows(&program.left, witness, program.rows);
+        let right = eval_rows(&program.right, witness, program.rows);
+        let output = eval_rows(&program.output, witness, program.rows);
 
-        let left_p = Poly::new(left.clone());
-        let right_p = Poly::new(right.clone());
-        let output_p = Poly::new(output.clone());
-        let ht = Poly::sub(Poly::mul(left_p, right_p), output_p);
-        let h = Poly::div(ht, program.t.clone());
-        if h.is_err() {
-            panic!("Bad witness");
-        }
-        let h = h.unwrap();
+        // h(x) = (L(x) * R(x) - O(x)) / t(x), computed on a coset where t(x) is non-zero
+        let domain = &program.domain;
+        let left_e = domain.coset_fft(&domain.ifft(&left)?)?;
+        let right_e = domain.coset_fft(&domain.ifft(&right)?)?;
+        let output_e = domain.coset_fft(&domain.ifft(&output)?)?;
+        let mut h: Vec<Scalar> = (0..program.rows)
+            .map(|i| left_e[i] * right_e[i] - output_e[i])
+            .collect();
+        domain.divide_by_vanishing_poly_on_coset(&mut h);
+        let h = domain.coset_ifft(&h)?;
 
-        let mut rng = rand::thread_rng();
-        let r = Scalar::from(rng.next_u64());
-        let s = Scalar::from(rng.next_u64());
+        let r = Scalar::random(&mut *rng);
+        let s = Scalar::random(&mut *rng);
 
-        let mut lg1 = G1Projective::from(setup.alpha) + r * setup.delta_1;
-        let mut rg1 = G1Projective::from(setup.beta_1) + s * setup.delta_1;
-        let mut rg2 = G2Projective::from(setup.beta_2) + s * setup.delta_2;
+        let mut lg1 = G1Projective::from(key.alpha) + r * key.delta_1;
+        let mut rg1 = G1Projective::from(key.beta_1) + s * key.delta_1;
+        let mut rg2 = G2Projective::from(key.beta_2) + s * key.delta_2;
+        let mut og1 = G1Projective::identity();
 
-        for i in 0..program.rows {
-            lg1 += setup.tau_g1[i] * left[i];
-            rg1 += setup.tau_g1[i] * right[i];
-            rg2 += setup.tau_g2[i] * right[i];
-            if h.0.len() > i {
-                og1 += h.0[i] * setup.t_tau_g1[i];
-            }
-        }
-        og1 += lg1 * s + rg1 * r - r * s * setup.delta_1;
+        witness.iter().enumerate().for_each(|(i, val)| {
+            lg1 += key.left_g1[i] * val;
+            rg1 += key.right_g1[i] * val;
+            rg2 += key.right_g2[i] * val;
+        });
+        program
+            .private_columns()
+            .iter()
+            .zip(&key.psi_prover)
+            .for_each(|(i, psi)| og1 += psi * witness[*i]);
+        key.t_tau_g1
+            .iter()
+            .zip(h.iter())
+            .for_each(|(t, val)| og1 += t * val);
+        og1 += lg1 * s + rg1 * r - r * s * key.delta_1;
 
-        (
-            G1Affine::from(lg1),
-            G2Affine::from(rg2),
-            G1Affine::from(og1),
-        )
+        Ok(Proof {
+            lg1: G1Affine::from(lg1),
+            rg2: G2Affine::from(rg2),
+            og1: G1Affine::from(og1),
+        })
     }
 }
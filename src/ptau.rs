@@ -0,0 +1,206 @@
+//! Reader for the snarkjs `.ptau` powers-of-tau format on the bls12381 curve.
+
+use crate::{
+    binfile::{self, q, BinFile, FQ_SIZE},
+    powers_of_tau::Accumulator,
+    program::Program,
+    serialize::DecodeError,
+    Error,
+};
+use bls12_381::{G1Affine, G2Affine};
+
+const MAGIC: &[u8; 4] = b"ptau";
+const VERSION: u32 = 1;
+
+const SECTION_HEADER: u32 = 1;
+const SECTION_TAU_G1: u32 = 2;
+const SECTION_TAU_G2: u32 = 3;
+const SECTION_ALPHA_TAU_G1: u32 = 4;
+const SECTION_BETA_TAU_G1: u32 = 5;
+const SECTION_BETA_G2: u32 = 6;
+const SECTION_CONTRIBUTIONS: u32 = 7;
+
+/// Reads the final accumulator of a ceremony. Contribution history and the
+/// precomputed Lagrange sections are ignored, and the points are not checked
+/// against each other; run the transcript through snarkjs to verify it.
+pub fn read(bytes: &[u8]) -> Result<Accumulator, DecodeError> {
+    let file = BinFile::parse(bytes, MAGIC)?;
+    if file.version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(file.version as u8));
+    }
+
+    let mut header = file.section(SECTION_HEADER)?;
+    if header.modulus(q())? != FQ_SIZE {
+        return Err(DecodeError::WrongCurve);
+    }
+    let power = header.u32()?;
+    if power > 28 {
+        return Err(DecodeError::InvalidPowers);
+    }
+    let size = 1usize << power;
+    if size < 2 {
+        return Err(DecodeError::InvalidPowers);
+    }
+
+    Ok(Accumulator {
+        tau_g1: file.section(SECTION_TAU_G1)?.g1_vec(2 * size - 1)?,
+        tau_g2: file.section(SECTION_TAU_G2)?.g2_vec(size)?,
+        alpha_tau_g1: file.section(SECTION_ALPHA_TAU_G1)?.g1_vec(size)?,
+        beta_tau_g1: file.section(SECTION_BETA_TAU_G1)?.g1_vec(size)?,
+        beta_g2: file.section(SECTION_BETA_G2)?.g2()?,
+    })
+}
+
+/// Reads an accumulator and checks that it covers every row of `program`.
+pub fn read_for_program(bytes: &[u8], program: &Program) -> Result<Accumulator, Error> {
+    let powers = read(bytes)?;
+    if powers.size() < program.rows {
+        return Err(Error::PowersTooSmall {
+            required: program.rows,
+            available: powers.size(),
+        });
+    }
+    Ok(powers)
+}
+
+/// Writes an accumulator in the `.ptau` layout with an empty contribution
+/// list. The accumulator must have a size that `Accumulator::new` accepts.
+pub fn write(powers: &Accumulator) -> Result<Vec<u8>, Error> {
+    let size = powers.size();
+    if !powers.has_size(size) {
+        return Err(Error::InvalidAccumulatorSize { size });
+    }
+    let power = size.trailing_zeros();
+
+    let mut header = (FQ_SIZE as u32).to_le_bytes().to_vec();
+    let mut modulus = q().to_bytes_le();
+    modulus.resize(FQ_SIZE, 0);
+    header.extend_from_slice(&modulus);
+    header.extend_from_slice(&power.to_le_bytes());
+    header.extend_from_slice(&power.to_le_bytes());
+
+    let g1 = |points: &[G1Affine]| {
+        let mut bytes = vec![];
+        points.iter().for_each(|p| binfile::write_g1(&mut bytes, p));
+        bytes
+    };
+    let g2 = |points: &[G2Affine]| {
+        let mut bytes = vec![];
+        points.iter().for_each(|p| binfile::write_g2(&mut bytes, p));
+        bytes
+    };
+
+    Ok(binfile::write(
+        MAGIC,
+        VERSION,
+        &[
+            (SECTION_HEADER, header),
+            (SECTION_TAU_G1, g1(&powers.tau_g1)),
+            (SECTION_TAU_G2, g2(&powers.tau_g2)),
+            (SECTION_ALPHA_TAU_G1, g1(&powers.alpha_tau_g1)),
+            (SECTION_BETA_TAU_G1, g1(&powers.beta_tau_g1)),
+            (SECTION_BETA_G2, g2(&[powers.beta_g2])),
+            (SECTION_CONTRIBUTIONS, 0u32.to_le_bytes().to_vec()),
+        ],
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field::from_i64, prover::Prover, setup::TrustedSetup, verifier::Verifier};
+    use bls12_381::Scalar;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    /// A power-2 file holding the accumulator after one contribution, drawn
+    /// from `ChaCha20Rng::seed_from_u64(2)`; it pins the layout `write` had
+    /// when it was generated rather than snarkjs's own output
+    const FIXTURE: &[u8] = include_bytes!("../fixtures/contributed_2.ptau");
+
+    fn program() -> Program {
+        // x * x = y, x * y = z
+        Program::new_sparse(
+            &[vec![(1, Scalar::one())], vec![(1, Scalar::one())]],
+            &[vec![(1, Scalar::one())], vec![(2, Scalar::one())]],
+            &[vec![(2, Scalar::one())], vec![(3, Scalar::one())]],
+            4,
+            2,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn ptau_fixture() {
+        let initial = Accumulator::new(4).unwrap();
+        let (_, contribution) = initial.contribute_with_rng(&mut ChaCha20Rng::seed_from_u64(2));
+        let powers = read(FIXTURE).unwrap();
+        assert!(initial.verify_contribution(&powers, &contribution));
+        assert!(!initial.verify_contribution(&initial, &contribution));
+
+        let program = program();
+        let powers = read_for_program(FIXTURE, &program).unwrap();
+        let rng = &mut ChaCha20Rng::seed_from_u64(1);
+        let (pk, vk) = TrustedSetup::from_powers(&program, &powers, rng).unwrap();
+        let witness = [1, 3, 9, 27].map(from_i64);
+        let proof = Prover::new(program, pk).unwrap().prove(&witness).unwrap();
+        assert_eq!(Verifier::new(vk).verify(&proof, &witness[1..2]), Ok(true));
+    }
+
+    #[test]
+    fn ptau_round_trip_and_prove() {
+        let rng = &mut ChaCha20Rng::seed_from_u64(1);
+        let powers = Accumulator::new(4).unwrap().contribute_with_rng(rng).0;
+        let bytes = write(&powers).unwrap();
+        let program = program();
+        let read = read_for_program(&bytes, &program).unwrap();
+        assert_eq!(read, powers);
+
+        let (pk, vk) = TrustedSetup::from_powers(&program, &read, rng).unwrap();
+        let witness = [1, 3, 9, 27].map(from_i64);
+        let proof = Prover::new(program, pk).unwrap().prove(&witness).unwrap();
+        assert_eq!(Verifier::new(vk).verify(&proof, &witness[1..2]), Ok(true));
+    }
+
+    #[test]
+    fn ptau_errors() {
+        let program =
+            Program::new_sparse(&vec![vec![]; 5], &vec![vec![]; 5], &vec![vec![]; 5], 1, 1)
+                .unwrap();
+        assert_eq!(
+            read_for_program(FIXTURE, &program).err(),
+            Some(Error::PowersTooSmall {
+                required: 8,
+                available: 4
+            })
+        );
+
+        // Three powers, which no .ptau header can describe
+        let mut odd = Accumulator::new(4).unwrap();
+        odd.tau_g1.truncate(5);
+        odd.tau_g2.truncate(3);
+        odd.alpha_tau_g1.truncate(3);
+        odd.beta_tau_g1.truncate(3);
+        assert_eq!(write(&odd), Err(Error::InvalidAccumulatorSize { size: 3 }));
+
+        assert_eq!(read(&FIXTURE[..100]), Err(DecodeError::UnexpectedEof));
+        assert_eq!(read(b"zkey"), Err(DecodeError::BadMagic));
+
+        // A different base field modulus, as for bn128 files
+        let mut other_curve = FIXTURE.to_vec();
+        other_curve[40] ^= 1;
+        assert_eq!(read(&other_curve), Err(DecodeError::WrongCurve));
+
+        // A point off the curve
+        let mut off_curve = FIXTURE.to_vec();
+        let tau_g1 = 12 + 12 + 60 + 12;
+        off_curve[tau_g1 + 96] ^= 1;
+        assert_eq!(read(&off_curve), Err(DecodeError::InvalidPoint));
+
+        // Dropping the beta section
+        let beta = 12 + 12 + 60 + 12 + 7 * 96 + 12 + 4 * 192 + 2 * (12 + 4 * 96);
+        let mut missing = FIXTURE[..beta].to_vec();
+        missing[8] = 5;
+        assert_eq!(read(&missing), Err(DecodeError::MissingSection(6)));
+    }
+}
@@ -0,0 +1,136 @@
+//! Reader for Circom's `.r1cs` constraint files over the bls12381 prime.
+
+use crate::{
+    binfile::{r, BinFile, Cursor, FR_SIZE},
+    program::{Program, SparseRow},
+    serialize::DecodeError,
+};
+
+const MAGIC: &[u8; 4] = b"r1cs";
+const VERSION: u32 = 1;
+
+const SECTION_HEADER: u32 = 1;
+const SECTION_CONSTRAINTS: u32 = 2;
+
+/// A compiled Circom circuit. Circom numbers its wires as the constant one,
+/// then the public outputs, public inputs, private inputs and internal
+/// signals, so the first `1 + public_outputs + public_inputs` columns of
+/// the program are its public prefix.
+pub struct R1cs {
+    pub program: Program,
+    pub public_outputs: usize,
+    pub public_inputs: usize,
+    pub private_inputs: usize,
+}
+
+pub fn read(bytes: &[u8]) -> Result<R1cs, DecodeError> {
+    let file = BinFile::parse(bytes, MAGIC)?;
+    if file.version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(file.version as u8));
+    }
+
+    let mut header = file.section(SECTION_HEADER)?;
+    if header.modulus(r())? != FR_SIZE {
+        return Err(DecodeError::WrongCurve);
+    }
+    let wires = header.u32()? as usize;
+    let public_outputs = header.u32()? as usize;
+    let public_inputs = header.u32()? as usize;
+    let private_inputs = header.u32()? as usize;
+    let _labels = header.u64()?;
+    let constraints = header.u32()? as usize;
+    let public = 1 + public_outputs + public_inputs;
+    if public + private_inputs > wires {
+        return Err(DecodeError::InvalidProgram);
+    }
+
+    let mut section = file.section(SECTION_CONSTRAINTS)?;
+    // Every constraint holds at least the three term counts
+    if constraints.saturating_mul(12) > section.remaining() {
+        return Err(DecodeError::UnexpectedEof);
+    }
+    let (mut left, mut right, mut output) = (vec![], vec![], vec![]);
+    for _ in 0..constraints {
+        left.push(read_combination(&mut section)?);
+        right.push(read_combination(&mut section)?);
+        output.push(read_combination(&mut section)?);
+    }
+
+    let program = Program::new_sparse(&left, &right, &output, wires, public)
+        .map_err(|_| DecodeError::InvalidProgram)?;
+    Ok(R1cs {
+        program,
+        public_outputs,
+        public_inputs,
+        private_inputs,
+    })
+}
+
+fn read_combination(section: &mut Cursor) -> Result<SparseRow, DecodeError> {
+    let terms = section.u32()? as usize;
+    if terms.saturating_mul(4 + FR_SIZE) > section.remaining() {
+        return Err(DecodeError::UnexpectedEof);
+    }
+    (0..terms)
+        .map(|_| Ok((section.u32()? as usize, section.fr()?)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field::from_i64, prover::Prover, setup::TrustedSetup, verifier::Verifier};
+    use bls12_381::Scalar;
+
+    /// `m <== a * b; out <== m * b + 3;` with `out` and `a` public, written
+    /// independently of this crate
+    const FIXTURE: &[u8] = include_bytes!("../fixtures/circom/product.r1cs");
+
+    #[test]
+    fn r1cs_fixture() {
+        let r1cs = read(FIXTURE).unwrap();
+        assert_eq!(
+            (r1cs.public_outputs, r1cs.public_inputs, r1cs.private_inputs),
+            (1, 1, 1)
+        );
+        let program = r1cs.program;
+        assert_eq!(
+            (program.cols, program.public.len(), program.rows),
+            (5, 3, 2)
+        );
+        assert_eq!(
+            program.output[1],
+            vec![(1, Scalar::one()), (0, -from_i64(3))]
+        );
+
+        // one, out, a, b, m
+        let witness = [1, 53, 2, 5, 10].map(from_i64);
+        let (pk, vk) = TrustedSetup::new(&program);
+        let proof = Prover::new(program, pk).unwrap().prove(&witness).unwrap();
+        assert_eq!(Verifier::new(vk).verify(&proof, &witness[1..3]), Ok(true));
+    }
+
+    #[test]
+    fn r1cs_errors() {
+        assert_eq!(read(&FIXTURE[..60]).err(), Some(DecodeError::UnexpectedEof));
+        assert_eq!(read(b"wtns").err(), Some(DecodeError::BadMagic));
+
+        // The bn128 prime, as when Circom runs with its default field
+        let mut bn128 = FIXTURE.to_vec();
+        bn128[28..60].copy_from_slice(
+            &num_bigint::BigUint::parse_bytes(
+                b"21888242871839275222246405745257275088548364400416034343698204186575808495617",
+                10,
+            )
+            .unwrap()
+            .to_bytes_le(),
+        );
+        assert_eq!(read(&bn128).err(), Some(DecodeError::WrongCurve));
+
+        // A term referencing a wire past the end
+        let mut wire = FIXTURE.to_vec();
+        let constraints = 12 + 12 + 64 + 12;
+        wire[constraints + 4] = 5;
+        assert_eq!(read(&wire).err(), Some(DecodeError::InvalidProgram));
+    }
+}
@@ -0,0 +1,609 @@
+use crate::{
+    program::{Program, SparseRow},
+    prover::Proof,
+    setup::{ProvingKey, VerifyingKey},
+    Error,
+};
+use bls12_381::{G1Affine, G2Affine, Scalar};
+use std::fmt;
+
+// Every encoding starts with MAGIC || VERSION || kind || point encoding
+const MAGIC: [u8; 4] = *b"G16R";
+// Version 2 added labels to programs and version 3 the positions of their
+// public columns, which were the first ones before; older input is still read
+const VERSION: u8 = 3;
+
+const KIND_PROOF: u8 = 1;
+const KIND_PROVING_KEY: u8 = 2;
+const KIND_VERIFYING_KEY: u8 = 3;
+const KIND_PROGRAM: u8 = 4;
+
+/// How curve points are written.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Encoding {
+    Compressed,
+    Uncompressed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DecodeError {
+    UnexpectedEof,
+    BadMagic,
+    UnsupportedVersion(u8),
+    WrongKind {
+        expected: u8,
+        found: u8,
+    },
+    UnknownEncoding(u8),
+    InvalidPoint,
+    InvalidScalar,
+    InvalidProgram,
+    TrailingBytes,
+    /// A required section is missing from a snarkjs or Circom file.
+    MissingSection(u32),
+    /// The file is for a curve other than BLS12-381.
+    WrongCurve,
+    /// The key is for a proof system other than Groth16.
+    UnsupportedProtocol,
+    /// Malformed JSON, or JSON missing a field snarkjs writes.
+    InvalidJson(String),
+    /// The powers of tau in the file are too few or too many to use.
+    InvalidPowers,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DecodeError::UnexpectedEof => write!(f, "Unexpected end of input"),
+            DecodeError::BadMagic => write!(f, "Bad magic bytes"),
+            DecodeError::UnsupportedVersion(v) => write!(f, "Unsupported version {}", v),
+            DecodeError::WrongKind { expected, found } => {
+                write!(f, "Expected object kind {}, found {}", expected, found)
+            }
+            DecodeError::UnknownEncoding(e) => write!(f, "Unknown point encoding {}", e),
+            DecodeError::InvalidPoint => write!(f, "Point not on the curve or not in the subgroup"),
+            DecodeError::InvalidScalar => write!(f, "Non-canonical scalar"),
+            DecodeError::InvalidProgram => write!(f, "Inconsistent program"),
+            DecodeError::TrailingBytes => write!(f, "Trailing bytes after object"),
+            DecodeError::MissingSection(id) => write!(f, "Missing section {}", id),
+            DecodeError::WrongCurve => write!(f, "File is not for the BLS12-381 curve"),
+            DecodeError::UnsupportedProtocol => write!(f, "Unsupported proof system"),
+            DecodeError::InvalidJson(e) => write!(f, "Invalid JSON: {}", e),
+            DecodeError::InvalidPowers => write!(f, "Unsupported number of powers of tau"),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+impl Proof {
+    pub fn to_bytes(&self, encoding: Encoding) -> Vec<u8> {
+        let mut w = Writer::new(KIND_PROOF, encoding);
+        w.g1(&self.lg1);
+        w.g2(&self.rg2);
+        w.g1(&self.og1);
+        w.bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = Reader::new(bytes, KIND_PROOF)?;
+        let proof = Proof {
+            lg1: r.g1()?,
+            rg2: r.g2()?,
+            og1: r.g1()?,
+        };
+        r.finish()?;
+        Ok(proof)
+    }
+}
+
+impl ProvingKey {
+    pub fn to_bytes(&self, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        let mut w = Writer::new(KIND_PROVING_KEY, encoding);
+        w.g1(&self.alpha);
+        w.g1(&self.beta_1);
+        w.g2(&self.beta_2);
+        w.g1(&self.delta_1);
+        w.g2(&self.delta_2);
+        w.g1_vec(&self.left_g1);
+        w.g1_vec(&self.right_g1);
+        w.g2_vec(&self.right_g2);
+        w.g1_vec(&self.t_tau_g1);
+        w.g1_vec(&self.psi_prover);
+        w.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = Reader::new(bytes, KIND_PROVING_KEY)?;
+        let key = ProvingKey {
+            alpha: r.g1()?,
+            beta_1: r.g1()?,
+            beta_2: r.g2()?,
+            delta_1: r.g1()?,
+            delta_2: r.g2()?,
+            left_g1: r.g1_vec()?,
+            right_g1: r.g1_vec()?,
+            right_g2: r.g2_vec()?,
+            t_tau_g1: r.g1_vec()?,
+            psi_prover: r.g1_vec()?,
+        };
+        r.finish()?;
+        Ok(key)
+    }
+}
+
+impl VerifyingKey {
+    pub fn to_bytes(&self, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        let mut w = Writer::new(KIND_VERIFYING_KEY, encoding);
+        w.g1(&self.alpha);
+        w.g2(&self.beta_2);
+        w.g2(&self.gamma);
+        w.g2(&self.delta_2);
+        w.g1_vec(&self.psi_verifier);
+        w.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = Reader::new(bytes, KIND_VERIFYING_KEY)?;
+        let key = VerifyingKey {
+            alpha: r.g1()?,
+            beta_2: r.g2()?,
+            gamma: r.g2()?,
+            delta_2: r.g2()?,
+            psi_verifier: r.g1_vec()?,
+        };
+        r.finish()?;
+        Ok(key)
+    }
+}
+
+impl Program {
+    /// Programs hold no curve points, so `encoding` only ends up in the header.
+    pub fn to_bytes(&self, encoding: Encoding) -> Result<Vec<u8>, Error> {
+        let mut w = Writer::new(KIND_PROGRAM, encoding);
+        w.u32(self.cols);
+        w.u32(self.public.len());
+        w.u32(self.left.len());
+        self.public[1..].iter().for_each(|col| w.u32(*col));
+        for rows in [&self.left, &self.right, &self.output] {
+            rows.iter().for_each(|row| w.row(row));
+        }
+        for labels in [&self.column_labels, &self.constraint_labels] {
+            labels.iter().for_each(|label| w.label(label));
+        }
+        w.finish()
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let mut r = Reader::new(bytes, KIND_PROGRAM)?;
+        let cols = r.u32()?;
+        let public = r.u32()?;
+        let constraints = r.u32()?;
+        // Before version 3 the constant column could be left out as private
+        let public = if r.version < 3 { public.max(1) } else { public };
+        if cols == 0 || constraints == 0 || public > cols {
+            return Err(DecodeError::InvalidProgram);
+        }
+        let public_columns = if r.version >= 3 {
+            (1..public)
+                .map(|_| r.u32())
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            (1..public).collect()
+        };
+
+        let mut matrices = vec![];
+        for _ in 0..3 {
+            let rows = (0..constraints)
+                .map(|_| r.row(cols))
+                .collect::<Result<Vec<_>, _>>()?;
+            matrices.push(rows);
+        }
+        let (column_labels, constraint_labels) = if r.version >= 2 {
+            (
+                (0..cols).map(|_| r.label()).collect::<Result<_, _>>()?,
+                (0..constraints)
+                    .map(|_| r.label())
+                    .collect::<Result<_, _>>()?,
+            )
+        } else {
+            (vec![None; cols], vec![None; constraints])
+        };
+        r.finish()?;
+
+        let mut program =
+            Program::new_sparse(&matrices[0], &matrices[1], &matrices[2], cols, public)
+                .map_err(|_| DecodeError::InvalidProgram)?;
+        program
+            .set_public(&public_columns)
+            .map_err(|_| DecodeError::InvalidProgram)?;
+        program.column_labels = column_labels;
+        program.constraint_labels = constraint_labels;
+        Ok(program)
+    }
+}
+
+/// Lengths and column indices are written as `u32`, so encoding fails with
+/// `Error::TooLargeToEncode` rather than truncating any above `u32::MAX`.
+struct Writer {
+    bytes: Vec<u8>,
+    encoding: Encoding,
+    too_large: bool,
+}
+
+impl Writer {
+    fn new(kind: u8, encoding: Encoding) -> Self {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        bytes.push(kind);
+        bytes.push(match encoding {
+            Encoding::Uncompressed => 0,
+            Encoding::Compressed => 1,
+        });
+        Writer {
+            bytes,
+            encoding,
+            too_large: false,
+        }
+    }
+
+    fn finish(self) -> Result<Vec<u8>, Error> {
+        if self.too_large {
+            return Err(Error::TooLargeToEncode);
+        }
+        Ok(self.bytes)
+    }
+
+    fn u32(&mut self, value: usize) {
+        let value = u32::try_from(value).unwrap_or_else(|_| {
+            self.too_large = true;
+            u32::MAX
+        });
+        self.bytes.extend_from_slice(&value.to_le_bytes());
+    }
+
+    fn scalar(&mut self, value: &Scalar) {
+        self.bytes.extend_from_slice(&value.to_bytes());
+    }
+
+    fn g1(&mut self, point: &G1Affine) {
+        match self.encoding {
+            Encoding::Compressed => self.bytes.extend_from_slice(&point.to_compressed()),
+            Encoding::Uncompressed => self.bytes.extend_from_slice(&point.to_uncompressed()),
+        }
+    }
+
+    fn g2(&mut self, point: &G2Affine) {
+        match self.encoding {
+            Encoding::Compressed => self.bytes.extend_from_slice(&point.to_compressed()),
+            Encoding::Uncompressed => self.bytes.extend_from_slice(&point.to_uncompressed()),
+        }
+    }
+
+    fn g1_vec(&mut self, points: &[G1Affine]) {
+        self.u32(points.len());
+        points.iter().for_each(|p| self.g1(p));
+    }
+
+    fn g2_vec(&mut self, points: &[G2Affine]) {
+        self.u32(points.len());
+        points.iter().for_each(|p| self.g2(p));
+    }
+
+    /// A presence byte, then the length-prefixed UTF-8 bytes.
+    fn label(&mut self, label: &Option<String>) {
+        match label {
+            Some(label) => {
+                self.bytes.push(1);
+                self.u32(label.len());
+                self.bytes.extend_from_slice(label.as_bytes());
+            }
+            None => self.bytes.push(0),
+        }
+    }
+
+    fn row(&mut self, row: &SparseRow) {
+        self.u32(row.len());
+        row.iter().for_each(|(col, value)| {
+            self.u32(*col);
+            self.scalar(value);
+        });
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    version: u8,
+    encoding: Encoding,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8], kind: u8) -> Result<Self, DecodeError> {
+        let mut r = Reader {
+            bytes,
+            version: VERSION,
+            encoding: Encoding::Uncompressed,
+        };
+        if r.take(4)? != MAGIC {
+            return Err(DecodeError::BadMagic);
+        }
+        r.version = r.take(1)?[0];
+        if r.version == 0 || r.version > VERSION {
+            return Err(DecodeError::UnsupportedVersion(r.version));
+        }
+        let found = r.take(1)?[0];
+        if found != kind {
+            return Err(DecodeError::WrongKind {
+                expected: kind,
+                found,
+            });
+        }
+        r.encoding = match r.take(1)?[0] {
+            0 => Encoding::Uncompressed,
+            1 => Encoding::Compressed,
+            e => return Err(DecodeError::UnknownEncoding(e)),
+        };
+        Ok(r)
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], DecodeError> {
+        if self.bytes.len() < len {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn finish(self) -> Result<(), DecodeError> {
+        if !self.bytes.is_empty() {
+            return Err(DecodeError::TrailingBytes);
+        }
+        Ok(())
+    }
+
+    fn u32(&mut self) -> Result<usize, DecodeError> {
+        let bytes = self.take(4)?;
+        Ok(u32::from_le_bytes(bytes.try_into().unwrap()) as usize)
+    }
+
+    /// Reads a length prefix, rejecting lengths the remaining input cannot hold
+    /// before anything is allocated for them.
+    fn len(&mut self, item_size: usize) -> Result<usize, DecodeError> {
+        let len = self.u32()?;
+        if len.saturating_mul(item_size) > self.bytes.len() {
+            return Err(DecodeError::UnexpectedEof);
+        }
+        Ok(len)
+    }
+
+    fn scalar(&mut self) -> Result<Scalar, DecodeError> {
+        let bytes = self.take(32)?;
+        Option::from(Scalar::from_bytes(bytes.try_into().unwrap()))
+            .ok_or(DecodeError::InvalidScalar)
+    }
+
+    fn g1_size(&self) -> usize {
+        match self.encoding {
+            Encoding::Compressed => 48,
+            Encoding::Uncompressed => 96,
+        }
+    }
+
+    fn g1(&mut self) -> Result<G1Affine, DecodeError> {
+        let bytes = self.take(self.g1_size())?;
+        let point = match self.encoding {
+            Encoding::Compressed => G1Affine::from_compressed(bytes.try_into().unwrap()),
+            Encoding::Uncompressed => G1Affine::from_uncompressed(bytes.try_into().unwrap()),
+        };
+        Option::from(point).ok_or(DecodeError::InvalidPoint)
+    }
+
+    fn g2(&mut self) -> Result<G2Affine, DecodeError> {
+        let bytes = self.take(2 * self.g1_size())?;
+        let point = match self.encoding {
+            Encoding::Compressed => G2Affine::from_compressed(bytes.try_into().unwrap()),
+            Encoding::Uncompressed => G2Affine::from_uncompressed(bytes.try_into().unwrap()),
+        };
+        Option::from(point).ok_or(DecodeError::InvalidPoint)
+    }
+
+    fn g1_vec(&mut self) -> Result<Vec<G1Affine>, DecodeError> {
+        let len = self.len(self.g1_size())?;
+        (0..len).map(|_| self.g1()).collect()
+    }
+
+    fn g2_vec(&mut self) -> Result<Vec<G2Affine>, DecodeError> {
+        let len = self.len(2 * self.g1_size())?;
+        (0..len).map(|_| self.g2()).collect()
+    }
+
+    fn label(&mut self) -> Result<Option<String>, DecodeError> {
+        match self.take(1)?[0] {
+            0 => Ok(None),
+            1 => {
+                let len = self.len(1)?;
+                let bytes = self.take(len)?.to_vec();
+                String::from_utf8(bytes)
+                    .map(Some)
+                    .map_err(|_| DecodeError::InvalidProgram)
+            }
+            _ => Err(DecodeError::InvalidProgram),
+        }
+    }
+
+    fn row(&mut self, cols: usize) -> Result<SparseRow, DecodeError> {
+        let len = self.len(36)?;
+        (0..len)
+            .map(|_| {
+                let col = self.u32()?;
+                if col >= cols {
+                    return Err(DecodeError::InvalidProgram);
+                }
+                Ok((col, self.scalar()?))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field::from_i64, prover::Prover, setup::TrustedSetup, verifier::Verifier};
+
+    fn program() -> Program {
+        // x * x = y
+        Program::new_sparse(
+            &[vec![(1, Scalar::one())]],
+            &[vec![(1, Scalar::one())]],
+            &[vec![(2, Scalar::one())]],
+            3,
+            1,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn serialize_round_trip() {
+        let (proving_key, verifying_key) = TrustedSetup::new(&program());
+        let witness = [1, 3, 9].map(from_i64);
+
+        for encoding in [Encoding::Compressed, Encoding::Uncompressed] {
+            let program = Program::from_bytes(&program().to_bytes(encoding).unwrap()).unwrap();
+            let pk = ProvingKey::from_bytes(&proving_key.to_bytes(encoding).unwrap()).unwrap();
+            let vk = VerifyingKey::from_bytes(&verifying_key.to_bytes(encoding).unwrap()).unwrap();
+            assert_eq!(pk, proving_key);
+            assert_eq!(vk, verifying_key);
+
+            let proof = Prover::new(program, pk).unwrap().prove(&witness).unwrap();
+            let bytes = proof.to_bytes(encoding);
+            assert_eq!(Proof::from_bytes(&bytes), Ok(proof));
+            assert_eq!(
+                Verifier::new(vk).verify(&Proof::from_bytes(&bytes).unwrap(), &[]),
+                Ok(true)
+            );
+        }
+    }
+
+    #[test]
+    fn serialize_labels() {
+        let mut labelled = program();
+        labelled.label_column(1, "x");
+        labelled.label_column(2, "y");
+        labelled.label_constraint(0, "y = x*x");
+
+        let decoded =
+            Program::from_bytes(&labelled.to_bytes(Encoding::Compressed).unwrap()).unwrap();
+        assert_eq!(
+            decoded.column_labels,
+            [None, Some("x".into()), Some("y".into())]
+        );
+        assert_eq!(decoded.constraint_labels, [Some("y = x*x".into())]);
+
+        // Version 1 programs end after the matrices and have no labels
+        let bytes = program().to_bytes(Encoding::Compressed).unwrap();
+        let mut v1 = bytes[..bytes.len() - 4].to_vec();
+        v1[4] = 1;
+        let decoded = Program::from_bytes(&v1).unwrap();
+        assert_eq!(decoded.column_labels, [None, None, None]);
+        assert_eq!(decoded.left, program().left);
+    }
+
+    #[test]
+    fn serialize_too_large() {
+        let mut w = Writer::new(KIND_PROGRAM, Encoding::Compressed);
+        w.u32(u32::MAX as usize);
+        assert!(w.finish().is_ok());
+
+        let mut w = Writer::new(KIND_PROGRAM, Encoding::Compressed);
+        w.u32(u32::MAX as usize + 1);
+        assert_eq!(w.finish(), Err(Error::TooLargeToEncode));
+    }
+
+    #[test]
+    fn serialize_public_columns() {
+        let mut reordered = program();
+        reordered.set_public(&[2, 1]).unwrap();
+        let bytes = reordered.to_bytes(Encoding::Compressed).unwrap();
+        assert_eq!(Program::from_bytes(&bytes).unwrap().public, [0, 2, 1]);
+
+        // Version 2 programs have no positions, so their public columns come first
+        let v2 = Program::new_sparse(&program().left, &program().right, &program().output, 3, 3)
+            .unwrap()
+            .to_bytes(Encoding::Compressed)
+            .unwrap();
+        let mut v2 = [&v2[..19], &v2[27..]].concat();
+        v2[4] = 2;
+        assert_eq!(Program::from_bytes(&v2).unwrap().public, [0, 1, 2]);
+    }
+
+    #[test]
+    fn serialize_sizes() {
+        let (proving_key, _) = TrustedSetup::new(&program());
+        let proof = Prover::new(program(), proving_key)
+            .unwrap()
+            .prove(&[1, 3, 9].map(from_i64))
+            .unwrap();
+        assert_eq!(proof.to_bytes(Encoding::Compressed).len(), 7 + 48 + 96 + 48);
+        assert_eq!(
+            proof.to_bytes(Encoding::Uncompressed).len(),
+            7 + 96 + 192 + 96
+        );
+    }
+
+    #[test]
+    fn serialize_errors() {
+        let (proving_key, _) = TrustedSetup::new(&program());
+        let proof = Prover::new(program(), proving_key)
+            .unwrap()
+            .prove(&[1, 3, 9].map(from_i64))
+            .unwrap();
+        let bytes = proof.to_bytes(Encoding::Compressed);
+
+        assert_eq!(
+            Proof::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(DecodeError::UnexpectedEof)
+        );
+        assert_eq!(
+            Proof::from_bytes(&[bytes.clone(), vec![0]].concat()),
+            Err(DecodeError::TrailingBytes)
+        );
+        assert_eq!(
+            VerifyingKey::from_bytes(&bytes),
+            Err(DecodeError::WrongKind {
+                expected: KIND_VERIFYING_KEY,
+                found: KIND_PROOF
+            })
+        );
+
+        let mut bad = bytes.clone();
+        bad[0] = b'X';
+        assert_eq!(Proof::from_bytes(&bad), Err(DecodeError::BadMagic));
+
+        let mut bad = bytes.clone();
+        bad[4] = VERSION + 1;
+        assert_eq!(
+            Proof::from_bytes(&bad),
+            Err(DecodeError::UnsupportedVersion(VERSION + 1))
+        );
+
+        let mut bad = bytes.clone();
+        bad[6] = 2;
+        assert_eq!(
+            Proof::from_bytes(&bad),
+            Err(DecodeError::UnknownEncoding(2))
+        );
+
+        // Flipping a coordinate bit moves the point off the curve
+        let mut bad = bytes.clone();
+        bad[7 + 47] ^= 1;
+        assert_eq!(Proof::from_bytes(&bad), Err(DecodeError::InvalidPoint));
+
+        let mut bad = program().to_bytes(Encoding::Compressed).unwrap();
+        bad[7 + 12 + 4] = 3;
+        assert_eq!(
+            Program::from_bytes(&bad).err(),
+            Some(DecodeError::InvalidProgram)
+        );
+    }
+}
@@ -1,99 +1,223 @@
-use crate::program::Program;
-use bls12_381::{G1Affine, G2Affine, Scalar};
-use rand::RngCore;
+use crate::{
+    domain::FftElement,
+    powers_of_tau::Accumulator,
+    program::{Program, SparseRow},
+    utils::eval_columns,
+    Error,
+};
+use bls12_381::{G1Affine, G1Projective, G2Affine, G2Projective, Scalar};
+use ff::Field;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
 
-#[derive(Clone)]
-pub struct TrustedSetup {
+pub struct TrustedSetup;
+
+/// Everything the prover needs, with the column polynomials already
+/// evaluated at tau in the exponent.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProvingKey {
     pub alpha: G1Affine,
     pub beta_1: G1Affine,
     pub beta_2: G2Affine,
-    pub gamma: G2Affine,
     pub delta_1: G1Affine,
     pub delta_2: G2Affine,
-    pub tau_g1: Vec<G1Affine>,
-    pub tau_g2: Vec<G2Affine>,
+    pub left_g1: Vec<G1Affine>,
+    pub right_g1: Vec<G1Affine>,
+    pub right_g2: Vec<G2Affine>,
     pub t_tau_g1: Vec<G1Affine>,
-    pub psi_verifier: Vec<G1Affine>,
     pub psi_prover: Vec<G1Affine>,
 }
 
+/// The handful of points needed to check a proof against its public inputs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VerifyingKey {
+    pub alpha: G1Affine,
+    pub beta_2: G2Affine,
+    pub gamma: G2Affine,
+    pub delta_2: G2Affine,
+    pub psi_verifier: Vec<G1Affine>,
+}
+
 impl TrustedSetup {
-    pub fn new(c: &Program) -> Self {
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new(c: &Program) -> (ProvingKey, VerifyingKey) {
+        TrustedSetup::new_with_rng(c, &mut OsRng)
+    }
+
+    /// Samples the toxic waste from the given generator. Use `new` unless the
+    /// keys need to be reproducible, e.g. from a seeded RNG in tests.
+    pub fn new_with_rng(
+        c: &Program,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> (ProvingKey, VerifyingKey) {
         let g1 = G1Affine::generator();
         let g2 = G2Affine::generator();
 
-        let mut rng = rand::thread_rng();
-        let tau = Scalar::from(rng.next_u64());
-        let alpha = Scalar::from(rng.next_u64());
-        let beta = Scalar::from(rng.next_u64());
-        let gamma = Scalar::from(rng.next_u64());
-        let delta = Scalar::from(rng.next_u64());
+        let tau = Scalar::random(&mut *rng);
+        let alpha = Scalar::random(&mut *rng);
+        let beta = Scalar::random(&mut *rng);
+        let gamma = Scalar::random(&mut *rng);
+        let delta = Scalar::random(&mut *rng);
 
         let gamma_inv = gamma.invert().unwrap();
         let delta_inv = delta.invert().unwrap();
 
-        let tau_g1 = powers_g1(tau, c.rows, Scalar::one());
-        let tau_g2 = powers_g2(tau, c.rows, Scalar::one());
-        let t_tau_g1 = powers_g1(tau, c.rows, c.t.eval(tau) * delta_inv);
-
-        let mut psi_verifier = vec![];
-        let mut psi_prover = vec![];
-        for i in 0..c.cols {
-            let is_public = i < c.public;
-            if is_public {
-                psi_verifier.push(G1Affine::from(
-                    (alpha * c.right[i].eval(tau)
-                        + beta * c.left[i].eval(tau)
-                        + c.output[i].eval(tau))
-                        * gamma_inv
-                        * g1,
-                ));
-            } else {
-                psi_prover.push(G1Affine::from(
-                    (alpha * c.right[i].eval(tau)
-                        + beta * c.left[i].eval(tau)
-                        + c.output[i].eval(tau))
-                        * delta_inv
-                        * g1,
-                ));
-            };
-        }
-
-        TrustedSetup {
+        // h(x) has degree at most n - 2, so n - 1 powers are enough
+        let t_tau_g1 = powers_g1(tau, c.rows - 1, c.t.eval(tau) * delta_inv);
+
+        let lagrange = c.domain.lagrange_coefficients(tau);
+        let left = eval_columns(&c.left, c.cols, &lagrange);
+        let right = eval_columns(&c.right, c.cols, &lagrange);
+        let output = eval_columns(&c.output, c.cols, &lagrange);
+
+        let psi = |i: &usize, scale: Scalar| {
+            G1Affine::from((alpha * right[*i] + beta * left[*i] + output[*i]) * scale * g1)
+        };
+        let psi_verifier = c.public.iter().map(|i| psi(i, gamma_inv)).collect();
+        let psi_prover = c
+            .private_columns()
+            .iter()
+            .map(|i| psi(i, delta_inv))
+            .collect();
+
+        let proving_key = ProvingKey {
             alpha: G1Affine::from(alpha * g1),
             beta_1: G1Affine::from(beta * g1),
             beta_2: G2Affine::from(beta * g2),
-            gamma: G2Affine::from(gamma * g2),
             delta_1: G1Affine::from(delta * g1),
             delta_2: G2Affine::from(delta * g2),
-            tau_g1,
-            tau_g2,
+            left_g1: left.iter().map(|l| G1Affine::from(g1 * l)).collect(),
+            right_g1: right.iter().map(|r| G1Affine::from(g1 * r)).collect(),
+            right_g2: right.iter().map(|r| G2Affine::from(g2 * r)).collect(),
             t_tau_g1,
-            psi_verifier,
             psi_prover,
-        }
+        };
+        let verifying_key = VerifyingKey {
+            alpha: proving_key.alpha,
+            beta_2: proving_key.beta_2,
+            gamma: G2Affine::from(gamma * g2),
+            delta_2: proving_key.delta_2,
+            psi_verifier,
+        };
+        (proving_key, verifying_key)
+    }
+
+    /// Derives the keys from the output of a powers-of-tau ceremony, so tau,
+    /// alpha and beta are never known to anyone. Gamma and delta are still
+    /// sampled locally.
+    pub fn from_powers(
+        c: &Program,
+        powers: &Accumulator,
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<(ProvingKey, VerifyingKey), Error> {
+        let gamma = Scalar::random(&mut *rng);
+        let delta = Scalar::random(&mut *rng);
+        keys_from_powers(c, powers, gamma, delta)
     }
 }
 
+pub(crate) fn keys_from_powers(
+    c: &Program,
+    powers: &Accumulator,
+    gamma: Scalar,
+    delta: Scalar,
+) -> Result<(ProvingKey, VerifyingKey), Error> {
+    let n = c.rows;
+    if powers.size() < n {
+        return Err(Error::PowersTooSmall {
+            required: n,
+            available: powers.size(),
+        });
+    }
+    let g2 = G2Affine::generator();
+    let gamma_inv = gamma.invert().unwrap();
+    let delta_inv = delta.invert().unwrap();
+
+    // [L_j(tau)] for the Lagrange basis of the domain is the inverse FFT of [tau^j]
+    let domain = &c.domain;
+    let lagrange = |points: &[G1Affine]| {
+        let points: Vec<G1Projective> = points[..n].iter().map(G1Projective::from).collect();
+        domain.ifft(&points)
+    };
+    let lagrange_g1 = lagrange(&powers.tau_g1)?;
+    let alpha_lagrange_g1 = lagrange(&powers.alpha_tau_g1)?;
+    let beta_lagrange_g1 = lagrange(&powers.beta_tau_g1)?;
+    let lagrange_g2: Vec<G2Projective> = domain.ifft(
+        &powers.tau_g2[..n]
+            .iter()
+            .map(G2Projective::from)
+            .collect::<Vec<_>>(),
+    )?;
+
+    let left = combine_columns(&c.left, c.cols, &lagrange_g1);
+    let right = combine_columns(&c.right, c.cols, &lagrange_g1);
+    let psi: Vec<G1Projective> = combine_columns(&c.right, c.cols, &alpha_lagrange_g1)
+        .iter()
+        .zip(combine_columns(&c.left, c.cols, &beta_lagrange_g1))
+        .zip(combine_columns(&c.output, c.cols, &lagrange_g1))
+        .map(|((a, b), o)| a + b + o)
+        .collect();
+    let psi_verifier: Vec<G1Projective> = c.public.iter().map(|i| psi[*i] * gamma_inv).collect();
+    let psi_prover: Vec<G1Projective> = c
+        .private_columns()
+        .iter()
+        .map(|i| psi[*i] * delta_inv)
+        .collect();
+
+    // t(tau) * tau^i = tau^(i + n) - tau^i
+    let t_tau_g1: Vec<G1Projective> = (0..n - 1)
+        .map(|i| (powers.tau_g1[i + n] - G1Projective::from(powers.tau_g1[i])) * delta_inv)
+        .collect();
+
+    let proving_key = ProvingKey {
+        alpha: powers.alpha_tau_g1[0],
+        beta_1: powers.beta_tau_g1[0],
+        beta_2: powers.beta_g2,
+        delta_1: G1Affine::from(G1Affine::generator() * delta),
+        delta_2: G2Affine::from(g2 * delta),
+        left_g1: to_affine_g1(&left),
+        right_g1: to_affine_g1(&right),
+        right_g2: combine_columns(&c.right, c.cols, &lagrange_g2)
+            .iter()
+            .map(G2Affine::from)
+            .collect(),
+        t_tau_g1: to_affine_g1(&t_tau_g1),
+        psi_prover: to_affine_g1(&psi_prover),
+    };
+    let verifying_key = VerifyingKey {
+        alpha: proving_key.alpha,
+        beta_2: proving_key.beta_2,
+        gamma: G2Affine::from(g2 * gamma),
+        delta_2: proving_key.delta_2,
+        psi_verifier: to_affine_g1(&psi_verifier),
+    };
+    Ok((proving_key, verifying_key))
+}
+
+/// Combines per-row points into per-column points, `sum_j M[j][i] * points[j]`.
+pub(crate) fn combine_columns<T: FftElement>(
+    rows: &[SparseRow],
+    cols: usize,
+    points: &[T],
+) -> Vec<T> {
+    let mut values = vec![T::default(); cols];
+    rows.iter().enumerate().for_each(|(j, row)| {
+        row.iter()
+            .for_each(|(col, coeff)| values[*col] = values[*col] + points[j] * *coeff);
+    });
+    values
+}
+
+fn to_affine_g1(points: &[G1Projective]) -> Vec<G1Affine> {
+    points.iter().map(G1Affine::from).collect()
+}
+
 fn powers_g1(val: Scalar, len: usize, mul: Scalar) -> Vec<G1Affine> {
     let mut acc = Scalar::one();
     let g1 = G1Affine::generator();
     (0..len)
         .map(|_| {
             let res = G1Affine::from(acc * mul * g1);
-            acc = acc * val;
-            res
-        })
-        .collect()
-}
-
-fn powers_g2(val: Scalar, len: usize, mul: Scalar) -> Vec<G2Affine> {
-    let mut acc = Scalar::one();
-    let g2 = G2Affine::generator();
-    (0..len)
-        .map(|_| {
-            let res = G2Affine::from(acc * mul * g2);
-            acc = acc * val;
+            acc *= val;
             res
         })
         .collect()
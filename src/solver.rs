@@ -0,0 +1,162 @@
+use crate::{
+    program::{Program, SparseRow},
+    Error,
+};
+use bls12_381::Scalar;
+use std::collections::VecDeque;
+
+impl Program {
+    /// Completes a partial witness, given with `None` for every unknown
+    /// column. Constraints left with a single unknown are solved for it until
+    /// no more progress can be made; an unknown appearing in both `L` and `R`
+    /// of a row has two candidate values and is not solved from that row.
+    ///
+    /// The result is not checked against the constraints that were already
+    /// fully determined.
+    pub fn solve(&self, partial: &[Option<Scalar>]) -> Result<Vec<Scalar>, Error> {
+        if partial.len() != self.cols {
+            return Err(Error::WitnessSizeMismatch {
+                expected: self.cols,
+                found: partial.len(),
+            });
+        }
+
+        let witness = self.propagate(partial.to_vec());
+        let unsolved: Vec<usize> = (0..self.cols).filter(|i| witness[*i].is_none()).collect();
+        if !unsolved.is_empty() {
+            return Err(Error::UnsolvedWitness { columns: unsolved });
+        }
+        Ok(witness.into_iter().flatten().collect())
+    }
+
+    /// Fills in every column reachable through constraints with one unknown,
+    /// leaving the rest as `None`.
+    pub(crate) fn propagate(&self, mut witness: Vec<Option<Scalar>>) -> Vec<Option<Scalar>> {
+        let constraints = self.left.len();
+        let mut rows_of = vec![vec![]; self.cols];
+        for (i, row) in self
+            .left
+            .iter()
+            .chain(&self.right)
+            .chain(&self.output)
+            .enumerate()
+        {
+            row.iter()
+                .for_each(|(col, _)| rows_of[*col].push(i % constraints));
+        }
+
+        let mut queue: VecDeque<usize> = (0..constraints).collect();
+        while let Some(row) = queue.pop_front() {
+            if let Some((col, value)) = self.solve_row(row, &witness) {
+                witness[col] = Some(value);
+                queue.extend(&rows_of[col]);
+            }
+        }
+        witness
+    }
+
+    /// Solves `(a0 + a1 u) * (b0 + b1 u) = c0 + c1 u` for the only unknown
+    /// `u` of a row, when it is linear in `u`.
+    fn solve_row(&self, row: usize, witness: &[Option<Scalar>]) -> Option<(usize, Scalar)> {
+        let rows = [&self.left[row], &self.right[row], &self.output[row]];
+        let mut unknowns = rows
+            .iter()
+            .flat_map(|row| row.iter())
+            .filter(|(col, _)| witness[*col].is_none())
+            .map(|(col, _)| *col);
+        let unknown = unknowns.next()?;
+        if unknowns.any(|col| col != unknown) {
+            return None;
+        }
+
+        let split = |row: &SparseRow| {
+            row.iter().fold(
+                (Scalar::zero(), Scalar::zero()),
+                |(known, coeff), (col, c)| match witness[*col] {
+                    Some(value) => (known + c * value, coeff),
+                    None => (known, coeff + c),
+                },
+            )
+        };
+        let ((a0, a1), (b0, b1), (c0, c1)) = (split(rows[0]), split(rows[1]), split(rows[2]));
+        if a1 * b1 != Scalar::zero() {
+            return None;
+        }
+        let coeff = a1 * b0 + a0 * b1 - c1;
+        Option::from(coeff.invert()).map(|inv: Scalar| (unknown, (c0 - a0 * b0) * inv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{dsl::Circuit, field::from_i64, program::Program, Error};
+    use bls12_381::Scalar;
+
+    fn program() -> Program {
+        // y^2 = 4x^3 + 2z + 9 over [1, y, x, z, v1, v2]
+        let one = Scalar::one();
+        Program::new_sparse(
+            &[vec![(1, one)], vec![(2, one)], vec![(5, one)]],
+            &[vec![(1, one)], vec![(2, one)], vec![(2, from_i64(4))]],
+            &[
+                vec![(4, one)],
+                vec![(5, one)],
+                vec![(0, from_i64(-9)), (3, from_i64(-2)), (4, one)],
+            ],
+            6,
+            2,
+        )
+        .unwrap()
+    }
+
+    fn partial(values: &[Option<i64>]) -> Vec<Option<Scalar>> {
+        values.iter().map(|v| v.map(from_i64)).collect()
+    }
+
+    #[test]
+    fn solve_intermediates() {
+        let program = program();
+        let expected = [1, 5, 1, 6, 25, 1].map(from_i64).to_vec();
+        assert_eq!(
+            program.solve(&partial(&[Some(1), Some(5), Some(1), Some(6), None, None])),
+            Ok(expected.clone())
+        );
+
+        // z is linear in the last constraint once v1 and v2 are known
+        assert_eq!(
+            program.solve(&partial(&[Some(1), Some(5), Some(1), None, None, None])),
+            Ok(expected)
+        );
+    }
+
+    #[test]
+    fn solve_reports_undetermined() {
+        // x only appears in x * x and v2 * 4x, each with a second unknown
+        let program = program();
+        assert_eq!(
+            program.solve(&partial(&[Some(1), Some(5), None, Some(6), None, None])),
+            Err(Error::UnsolvedWitness {
+                columns: vec![2, 5]
+            })
+        );
+        assert_eq!(
+            program.solve(&partial(&[Some(1)])),
+            Err(Error::WitnessSizeMismatch {
+                expected: 6,
+                found: 1
+            })
+        );
+    }
+
+    #[test]
+    fn solve_compiled_circuit() {
+        let circuit = Circuit::parse("public y; private x; y == x^3 + x + 5;").unwrap();
+        let program = circuit.program().unwrap();
+        let mut known = partial(&[Some(1), Some(35), Some(3)]);
+        known.resize(program.cols, None);
+        assert_eq!(
+            program.solve(&known),
+            circuit.witness(&[from_i64(35)], &[from_i64(3)])
+        );
+    }
+}
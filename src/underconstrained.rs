@@ -0,0 +1,136 @@
+//! A testing aid that looks for a second witness sharing the public inputs of
+//! a valid one. Finding one shows the circuit does not pin down its private
+//! variables, so a proof says less than the circuit author intended.
+
+use crate::{program::Program, Error};
+use bls12_381::Scalar;
+use ff::Field;
+use rand::{rngs::OsRng, CryptoRng, RngCore};
+
+/// Random values tried for each private column.
+const ATTEMPTS: usize = 4;
+
+/// Two witnesses that agree on every public column and both satisfy the program.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AlternativeWitness {
+    pub original: Vec<Scalar>,
+    pub alternative: Vec<Scalar>,
+    /// The private column whose perturbation led to `alternative`.
+    pub perturbed: usize,
+}
+
+impl Program {
+    /// Searches for a witness other than `witness` with the same public
+    /// inputs. `Ok(None)` means none was found, not that none exists.
+    pub fn find_alternative_witness(
+        &self,
+        witness: &[Scalar],
+    ) -> Result<Option<AlternativeWitness>, Error> {
+        self.find_alternative_witness_with_rng(witness, &mut OsRng)
+    }
+
+    /// Like `find_alternative_witness`, drawing perturbations from the given
+    /// generator.
+    ///
+    /// Each private column in turn is set to a random value with the public
+    /// columns fixed. The columns that follow from those through constraints
+    /// with one unknown are solved for, and any left over keep their value
+    /// from `witness`, one at a time, until the witness is complete.
+    pub fn find_alternative_witness_with_rng(
+        &self,
+        witness: &[Scalar],
+        rng: &mut (impl RngCore + CryptoRng),
+    ) -> Result<Option<AlternativeWitness>, Error> {
+        self.check_witness(witness)?;
+
+        for perturbed in self.private_columns() {
+            for _ in 0..ATTEMPTS {
+                let mut partial = vec![None; self.cols];
+                self.public
+                    .iter()
+                    .for_each(|i| partial[*i] = Some(witness[*i]));
+                partial[perturbed] = Some(Scalar::random(&mut *rng));
+
+                let mut partial = self.propagate(partial);
+                while let Some(col) = partial.iter().position(|v| v.is_none()) {
+                    partial[col] = Some(witness[col]);
+                    partial = self.propagate(partial);
+                }
+
+                let alternative: Vec<Scalar> = partial.into_iter().flatten().collect();
+                if alternative != witness && self.check_witness(&alternative).is_ok() {
+                    return Ok(Some(AlternativeWitness {
+                        original: witness.to_vec(),
+                        alternative,
+                        perturbed,
+                    }));
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dsl::Circuit, field::from_i64};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn search(source: &str, public: &[i64], private: &[i64]) -> Option<AlternativeWitness> {
+        let circuit = Circuit::parse(source).unwrap();
+        let witness = circuit
+            .witness(
+                &public.iter().map(|v| from_i64(*v)).collect::<Vec<_>>(),
+                &private.iter().map(|v| from_i64(*v)).collect::<Vec<_>>(),
+            )
+            .unwrap();
+        let program = circuit.program().unwrap();
+        program
+            .find_alternative_witness_with_rng(&witness, &mut ChaCha20Rng::seed_from_u64(1))
+            .unwrap()
+    }
+
+    #[test]
+    fn finds_free_private_variable() {
+        // z can absorb any change of x
+        let found = search(
+            "public y; private x, z; y^2 == 4*x^3 + 2*z + 9;",
+            &[5],
+            &[1, 6],
+        )
+        .unwrap();
+        assert_eq!(found.perturbed, 2);
+        assert_eq!(found.alternative[..2], found.original[..2]);
+        assert_ne!(found.alternative[3], found.original[3]);
+    }
+
+    #[test]
+    fn constrained_circuit_has_no_alternative() {
+        assert_eq!(
+            search("public y; private x; y == x^3 + x + 5;", &[35], &[3]),
+            None
+        );
+        assert_eq!(
+            search(
+                "public a, b; private x; a * x == b; x * x == 4;",
+                &[3, 6],
+                &[2]
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn rejects_bad_witness() {
+        let program = Circuit::parse("public y; private x; y == x * x;")
+            .unwrap()
+            .program()
+            .unwrap();
+        assert!(matches!(
+            program.find_alternative_witness(&[1, 4, 3].map(from_i64)),
+            Err(Error::BadWitness { .. })
+        ));
+    }
+}
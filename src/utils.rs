@@ -1,65 +1,52 @@
-use crate::poly::Poly;
+use crate::program::SparseRow;
 use bls12_381::Scalar;
 
-pub fn to_scalar(input: &[Vec<i64>]) -> Vec<Vec<Scalar>> {
+pub fn to_sparse(input: &[Vec<Scalar>]) -> Vec<SparseRow> {
     input
         .iter()
         .map(|row| {
             row.iter()
-                .map(|col| {
-                    let abs = col.abs();
-                    if abs > *col {
-                        Scalar::from(abs as u64).neg()
-                    } else {
-                        Scalar::from(abs as u64)
-                    }
-                })
+                .enumerate()
+                .filter(|(_, value)| **value != Scalar::zero())
+                .map(|(col, value)| (col, *value))
                 .collect()
         })
         .collect()
 }
 
-pub fn witness_to_scalar(witness: &[i64]) -> Vec<Scalar> {
-    witness
-        .iter()
-        .map(|col| {
-            let abs = col.abs();
-            if abs > *col {
-                Scalar::from(abs as u64).neg()
-            } else {
-                Scalar::from(abs as u64)
-            }
-        })
-        .collect()
+/// Sorts a row's entries by column, merging repeated columns and dropping
+/// zeros, so rows describing the same linear combination compare equal.
+pub fn normalize_row(row: &SparseRow) -> SparseRow {
+    let mut row = row.clone();
+    row.sort_by_key(|(col, _)| *col);
+    let mut merged: SparseRow = vec![];
+    for (col, value) in row {
+        match merged.last_mut() {
+            Some((last, sum)) if *last == col => *sum += value,
+            _ => merged.push((col, value)),
+        }
+    }
+    merged.retain(|(_, value)| *value != Scalar::zero());
+    merged
 }
 
-pub fn to_poly(
-    left: &[Vec<Scalar>],
-    right: &[Vec<Scalar>],
-    output: &[Vec<Scalar>],
-) -> (Vec<Poly>, Vec<Poly>, Vec<Poly>) {
-    let domain: Vec<Scalar> = (0..left.len())
-        .map(|value| Scalar::from(value as u64 + 1))
+/// Evaluates `<row, witness>` for every constraint, padding up to `size` rows with zeros.
+pub fn eval_rows(rows: &[SparseRow], witness: &[Scalar], size: usize) -> Vec<Scalar> {
+    let mut values: Vec<Scalar> = rows
+        .iter()
+        .map(|row| row.iter().map(|(col, coeff)| coeff * witness[*col]).sum())
         .collect();
+    values.resize(size, Scalar::zero());
+    values
+}
 
-    let mut poly_left = vec![];
-    let mut poly_right = vec![];
-    let mut poly_output = vec![];
-
-    for column in 0..left[0].len() {
-        poly_left.push(Poly::interpolate(
-            domain.clone(),
-            left.iter().map(|row| row[column]).collect(),
-        ));
-        poly_right.push(Poly::interpolate(
-            domain.clone(),
-            right.iter().map(|row| row[column]).collect(),
-        ));
-        poly_output.push(Poly::interpolate(
-            domain.clone(),
-            output.iter().map(|row| row[column]).collect(),
-        ));
-    }
-
-    (poly_left, poly_right, poly_output)
+/// Evaluates the interpolated polynomial of every column at the point whose
+/// Lagrange coefficients over the domain are given.
+pub fn eval_columns(rows: &[SparseRow], cols: usize, lagrange: &[Scalar]) -> Vec<Scalar> {
+    let mut values = vec![Scalar::zero(); cols];
+    rows.iter().enumerate().for_each(|(j, row)| {
+        row.iter()
+            .for_each(|(col, coeff)| values[*col] += coeff * lagrange[j]);
+    });
+    values
 }
@@ -1,37 +1,41 @@
-use bls12_381::{pairing, G1Affine, G1Projective, G2Affine};
+use bls12_381::{pairing, G1Affine, G1Projective, Scalar};
 
-use crate::{setup::TrustedSetup, utils::witness_to_scalar};
+use crate::{prover::Proof, setup::VerifyingKey, Error};
 
 pub struct Verifier {
-    setup: TrustedSetup,
+    key: VerifyingKey,
 }
 
 impl Verifier {
-    pub fn new(setup: TrustedSetup) -> Self {
-        Verifier { setup }
+    pub fn new(key: VerifyingKey) -> Self {
+        Verifier { key }
     }
 
-    pub fn verify(&self, lg1: G1Affine, rg2: G2Affine, og1: G1Affine, public_inputs: &[i64]) {
-        if public_inputs.len() != self.setup.psi_verifier.len() {
-            panic!("Bad public input length");
+    /// Checks a proof against the public inputs, without the constant one
+    /// that leads the witness.
+    pub fn verify(&self, proof: &Proof, public_inputs: &[Scalar]) -> Result<bool, Error> {
+        let expected = self.key.psi_verifier.len().saturating_sub(1);
+        if public_inputs.len() != expected || self.key.psi_verifier.is_empty() {
+            return Err(Error::BadPublicInputLength {
+                expected,
+                found: public_inputs.len(),
+            });
         }
 
-        let left = pairing(&lg1, &rg2);
-        let setup = &self.setup;
+        let left = pairing(&proof.lg1, &proof.rg2);
+        let key = &self.key;
 
-        let mut verifier_og1 = G1Projective::identity();
-        let public_inputs = witness_to_scalar(public_inputs);
+        // The constant column's point is the base, the inputs scale the rest
+        let mut verifier_og1 = G1Projective::from(key.psi_verifier[0]);
         public_inputs
             .iter()
-            .enumerate()
-            .for_each(|(i, input)| verifier_og1 += input * setup.psi_verifier[i]);
-        let prover_og1 = pairing(&og1, &setup.delta_2);
-        let verifier_og1 = pairing(&G1Affine::from(verifier_og1), &setup.gamma);
+            .zip(&key.psi_verifier[1..])
+            .for_each(|(input, psi)| verifier_og1 += psi * input);
+        let prover_og1 = pairing(&proof.og1, &key.delta_2);
+        let verifier_og1 = pairing(&G1Affine::from(verifier_og1), &key.gamma);
         let og1 = prover_og1 + verifier_og1;
 
-        let right = pairing(&setup.alpha, &setup.beta_2) + og1;
-        if left != right {
-            panic!("Bad proof");
-        }
+        let right = pairing(&key.alpha, &key.beta_2) + og1;
+        Ok(left == right)
     }
 }
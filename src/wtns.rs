@@ -0,0 +1,94 @@
+//! Reader for the `.wtns` witness files written by Circom's witness calculators.
+
+use crate::{
+    binfile::{r, BinFile, FR_SIZE},
+    program::Program,
+    serialize::DecodeError,
+    Error,
+};
+use bls12_381::Scalar;
+
+const MAGIC: &[u8; 4] = b"wtns";
+const VERSION: u32 = 2;
+
+const SECTION_HEADER: u32 = 1;
+const SECTION_WITNESS: u32 = 2;
+
+/// Reads the witness, one value per wire starting with the constant one.
+pub fn read(bytes: &[u8]) -> Result<Vec<Scalar>, DecodeError> {
+    let file = BinFile::parse(bytes, MAGIC)?;
+    if file.version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(file.version as u8));
+    }
+
+    let mut header = file.section(SECTION_HEADER)?;
+    if header.modulus(r())? != FR_SIZE {
+        return Err(DecodeError::WrongCurve);
+    }
+    let len = header.u32()? as usize;
+
+    let mut section = file.section(SECTION_WITNESS)?;
+    if len.saturating_mul(FR_SIZE) > section.remaining() {
+        return Err(DecodeError::UnexpectedEof);
+    }
+    (0..len).map(|_| section.fr()).collect()
+}
+
+/// Reads the witness and checks it has one value per column of `program`.
+pub fn read_for_program(bytes: &[u8], program: &Program) -> Result<Vec<Scalar>, Error> {
+    let witness = read(bytes)?;
+    if witness.len() != program.cols {
+        return Err(Error::WitnessSizeMismatch {
+            expected: program.cols,
+            found: witness.len(),
+        });
+    }
+    Ok(witness)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{field::from_i64, prover::Prover, r1cs, setup::TrustedSetup, verifier::Verifier};
+
+    /// The witness for `a = 2, b = 5` of the `product.r1cs` circuit
+    const FIXTURE: &[u8] = include_bytes!("../fixtures/circom/product.wtns");
+    const R1CS: &[u8] = include_bytes!("../fixtures/circom/product.r1cs");
+
+    #[test]
+    fn wtns_circom_workflow() {
+        let program = r1cs::read(R1CS).unwrap().program;
+        let witness = read_for_program(FIXTURE, &program).unwrap();
+        assert_eq!(witness, [1, 53, 2, 5, 10].map(from_i64));
+
+        let (pk, vk) = TrustedSetup::new(&program);
+        let public = program.public_inputs(&witness).unwrap();
+        let proof = Prover::new(program, pk).unwrap().prove(&witness).unwrap();
+        assert_eq!(Verifier::new(vk).verify(&proof, &public), Ok(true));
+    }
+
+    #[test]
+    fn wtns_errors() {
+        let program = Program::new_sparse(&[vec![]], &[vec![]], &[vec![]], 4, 1).unwrap();
+        assert_eq!(
+            read_for_program(FIXTURE, &program).err(),
+            Some(Error::WitnessSizeMismatch {
+                expected: 4,
+                found: 5
+            })
+        );
+
+        assert_eq!(read(&FIXTURE[..100]), Err(DecodeError::UnexpectedEof));
+        assert_eq!(read(R1CS), Err(DecodeError::BadMagic));
+
+        let mut bn128 = FIXTURE.to_vec();
+        bn128[28] ^= 1;
+        assert_eq!(read(&bn128), Err(DecodeError::WrongCurve));
+
+        // A value at or above the modulus
+        let mut value = FIXTURE.to_vec();
+        let witness = 12 + 12 + 40 + 12;
+        value[witness..witness + 32].copy_from_slice(&[0xff; 32]);
+        assert_eq!(read(&value), Err(DecodeError::InvalidScalar));
+    }
+}
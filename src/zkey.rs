@@ -0,0 +1,509 @@
+//! Reader and writer for snarkjs `.zkey` Groth16 keys on the bls12381 curve.
+
+use crate::{
+    binfile::{self, q, r, BinFile, Cursor, FQ_SIZE, FR_SIZE},
+    domain::{root_of_unity, EvaluationDomain},
+    program::{Program, SparseRow},
+    serialize::DecodeError,
+    setup::{ProvingKey, VerifyingKey},
+    utils::normalize_row,
+    Error,
+};
+use bls12_381::{G1Affine, G1Projective, Scalar};
+use ff::PrimeField;
+
+const MAGIC: &[u8; 4] = b"zkey";
+const VERSION: u32 = 1;
+const GROTH16: u32 = 1;
+
+const SECTION_HEADER: u32 = 1;
+const SECTION_GROTH16_HEADER: u32 = 2;
+const SECTION_IC: u32 = 3;
+const SECTION_COEFFS: u32 = 4;
+const SECTION_A: u32 = 5;
+const SECTION_B1: u32 = 6;
+const SECTION_B2: u32 = 7;
+const SECTION_C: u32 = 8;
+const SECTION_H: u32 = 9;
+const SECTION_CONTRIBUTIONS: u32 = 10;
+
+/// The program snarkjs derives a `.zkey` for: `c` followed by one
+/// `w_s * 0 = 0` row per public column `s`, which keep the public columns'
+/// polynomials independent. Keys written to or read from a `.zkey` belong
+/// to this program, so generate them and prove with it.
+pub fn with_input_rows(c: &Program) -> Result<Program, Error> {
+    if !c.has_public_prefix() {
+        return Err(Error::PublicNotPrefix);
+    }
+    let public = c.public.len();
+    let mut left = c.left.clone();
+    left.extend((0..public).map(|s| vec![(s, Scalar::one())]));
+    let mut right = c.right.clone();
+    right.resize(left.len(), vec![]);
+    let mut output = c.output.clone();
+    output.resize(left.len(), vec![]);
+
+    let mut program = Program::new_sparse(&left, &right, &output, c.cols, public)?;
+    program.column_labels = c.column_labels.clone();
+    program.constraint_labels[..c.constraint_labels.len()].clone_from_slice(&c.constraint_labels);
+    Ok(program)
+}
+
+/// Writes the keys in the `.zkey` layout. The keys must have been generated
+/// for `with_input_rows(c)`, whose L and R matrices are stored as the A and
+/// B coefficients.
+///
+/// The H query holds one point more than `t_tau_g1`, the coefficient of
+/// `x^(n-1)` that no valid proof uses, and it is written as if that point
+/// were the identity. The circuit hash and contribution list are left empty,
+/// so snarkjs will prove with the file but not verify it against a `.ptau`.
+pub fn write(c: &Program, pk: &ProvingKey, vk: &VerifyingKey) -> Result<Vec<u8>, Error> {
+    let c = &with_input_rows(c)?;
+    let n = c.rows;
+    if pk.left_g1.len() != c.cols
+        || pk.right_g1.len() != c.cols
+        || pk.right_g2.len() != c.cols
+        || pk.psi_prover.len() + c.public.len() != c.cols
+        || vk.psi_verifier.len() != c.public.len()
+        || pk.t_tau_g1.len() + 1 != n
+    {
+        return Err(Error::KeyMismatch);
+    }
+    let layout = Layout::new(&c.domain);
+
+    let mut header = vec![];
+    write_modulus(&mut header, &q().to_bytes_le(), FQ_SIZE);
+    write_modulus(&mut header, &r().to_bytes_le(), FR_SIZE);
+    for value in [c.cols, c.public.len() - 1, n] {
+        header.extend_from_slice(&(value as u32).to_le_bytes());
+    }
+    binfile::write_g1(&mut header, &pk.alpha);
+    binfile::write_g1(&mut header, &pk.beta_1);
+    binfile::write_g2(&mut header, &pk.beta_2);
+    binfile::write_g2(&mut header, &vk.gamma);
+    binfile::write_g1(&mut header, &pk.delta_1);
+    binfile::write_g2(&mut header, &pk.delta_2);
+
+    let mut coeffs = vec![];
+    let mut count = 0u32;
+    for (matrix, rows) in [&c.left, &c.right].into_iter().enumerate() {
+        for (row, entries) in rows.iter().enumerate() {
+            for (col, value) in entries {
+                coeffs.extend_from_slice(&(matrix as u32).to_le_bytes());
+                coeffs.extend_from_slice(&(row as u32).to_le_bytes());
+                coeffs.extend_from_slice(&(*col as u32).to_le_bytes());
+                coeffs.extend_from_slice(&(value * layout.montgomery_squared).to_bytes());
+                count += 1;
+            }
+        }
+    }
+    coeffs.splice(0..0, count.to_le_bytes());
+
+    // Undo ours_k = -2 g^k FFT(H)_k, see `read_h`
+    let mut transformed = vec![G1Projective::identity(); n];
+    let coset_inv = layout.coset.invert().unwrap();
+    let mut shift = (-Scalar::from(2)).invert().unwrap();
+    for (k, point) in pk.t_tau_g1.iter().enumerate() {
+        transformed[k] = point * shift;
+        shift *= coset_inv;
+    }
+    let h: Vec<G1Affine> = c
+        .domain
+        .ifft(&transformed)?
+        .iter()
+        .map(G1Affine::from)
+        .collect();
+
+    let mut contributions = vec![0u8; 64];
+    contributions.extend_from_slice(&0u32.to_le_bytes());
+
+    Ok(binfile::write(
+        MAGIC,
+        VERSION,
+        &[
+            (SECTION_HEADER, GROTH16.to_le_bytes().to_vec()),
+            (SECTION_GROTH16_HEADER, header),
+            (SECTION_IC, g1_bytes(&vk.psi_verifier)),
+            (SECTION_COEFFS, coeffs),
+            (SECTION_A, g1_bytes(&pk.left_g1)),
+            (SECTION_B1, g1_bytes(&pk.right_g1)),
+            (SECTION_B2, {
+                let mut bytes = vec![];
+                pk.right_g2
+                    .iter()
+                    .for_each(|p| binfile::write_g2(&mut bytes, p));
+                bytes
+            }),
+            (SECTION_C, g1_bytes(&pk.psi_prover)),
+            (SECTION_H, g1_bytes(&h)),
+            (SECTION_CONTRIBUTIONS, contributions),
+        ],
+    ))
+}
+
+/// Reads the keys from a `.zkey` file. The A and B coefficients are checked
+/// for shape but otherwise ignored; use `read_for_program` to check them
+/// against a program.
+pub fn read(bytes: &[u8]) -> Result<(ProvingKey, VerifyingKey), DecodeError> {
+    read_with_coefficients(bytes).map(|(pk, vk, _)| (pk, vk))
+}
+
+/// Reads the keys and checks that they were generated for
+/// `with_input_rows(c)`: the column and public input counts, the domain size
+/// and the A and B coefficients must all match.
+pub fn read_for_program(bytes: &[u8], c: &Program) -> Result<(ProvingKey, VerifyingKey), Error> {
+    let c = &with_input_rows(c)?;
+    let (pk, vk, coefficients) = read_with_coefficients(bytes)?;
+    if pk.left_g1.len() != c.cols
+        || vk.psi_verifier.len() != c.public.len()
+        || pk.t_tau_g1.len() + 1 != c.rows
+        || coefficients != [&c.left, &c.right].map(|rows| normalize(rows, c.rows))
+    {
+        return Err(Error::KeyMismatch);
+    }
+    Ok((pk, vk))
+}
+
+type Coefficients = [Vec<SparseRow>; 2];
+
+fn read_with_coefficients(
+    bytes: &[u8],
+) -> Result<(ProvingKey, VerifyingKey, Coefficients), DecodeError> {
+    let file = BinFile::parse(bytes, MAGIC)?;
+    if file.version != VERSION {
+        return Err(DecodeError::UnsupportedVersion(file.version as u8));
+    }
+    if file.section(SECTION_HEADER)?.u32()? != GROTH16 {
+        return Err(DecodeError::UnsupportedProtocol);
+    }
+
+    let mut header = file.section(SECTION_GROTH16_HEADER)?;
+    if header.modulus(q())? != FQ_SIZE || header.modulus(r())? != FR_SIZE {
+        return Err(DecodeError::WrongCurve);
+    }
+    let cols = header.u32()? as usize;
+    let public = header.u32()? as usize + 1;
+    let n = header.u32()? as usize;
+    if cols < public || !n.is_power_of_two() || n.trailing_zeros() >= Scalar::S {
+        return Err(DecodeError::InvalidProgram);
+    }
+    let alpha = header.g1()?;
+    let beta_1 = header.g1()?;
+    let beta_2 = header.g2()?;
+    let gamma = header.g2()?;
+    let delta_1 = header.g1()?;
+    let delta_2 = header.g2()?;
+
+    let domain = EvaluationDomain::new(n).map_err(|_| DecodeError::InvalidProgram)?;
+    let layout = Layout::new(&domain);
+    let coefficients = read_coefficients(&mut file.section(SECTION_COEFFS)?, &layout, cols)?;
+
+    let h = file.section(SECTION_H)?.g1_vec(n)?;
+    let proving_key = ProvingKey {
+        alpha,
+        beta_1,
+        beta_2,
+        delta_1,
+        delta_2,
+        left_g1: file.section(SECTION_A)?.g1_vec(cols)?,
+        right_g1: file.section(SECTION_B1)?.g1_vec(cols)?,
+        right_g2: file.section(SECTION_B2)?.g2_vec(cols)?,
+        t_tau_g1: read_h(&h, &domain, &layout)?,
+        psi_prover: file.section(SECTION_C)?.g1_vec(cols - public)?,
+    };
+    let verifying_key = VerifyingKey {
+        alpha,
+        beta_2,
+        gamma,
+        delta_2,
+        psi_verifier: file.section(SECTION_IC)?.g1_vec(public)?,
+    };
+    Ok((proving_key, verifying_key, coefficients))
+}
+
+fn read_coefficients(
+    section: &mut Cursor,
+    layout: &Layout,
+    cols: usize,
+) -> Result<Coefficients, DecodeError> {
+    let count = section.u32()? as usize;
+    if count.saturating_mul(12 + FR_SIZE) > section.remaining() {
+        return Err(DecodeError::UnexpectedEof);
+    }
+    let mut matrices = [vec![vec![]; layout.size], vec![vec![]; layout.size]];
+    for _ in 0..count {
+        let matrix = section.u32()? as usize;
+        let row = section.u32()? as usize;
+        let col = section.u32()? as usize;
+        let bytes: [u8; 32] = section.take(FR_SIZE)?.try_into().unwrap();
+        let value =
+            Option::<Scalar>::from(Scalar::from_bytes(&bytes)).ok_or(DecodeError::InvalidScalar)?;
+        if matrix > 1 || row >= layout.size || col >= cols {
+            return Err(DecodeError::InvalidProgram);
+        }
+        matrices[matrix][row].push((col, value * layout.montgomery_squared_inv));
+    }
+    Ok(matrices.map(|rows| normalize(&rows, layout.size)))
+}
+
+/// snarkjs holds the quotient query in the Lagrange basis of the odd points
+/// `g * w^i` of the domain of size `2n`, `H_i = L_{2i+1}(tau) / delta`. As
+/// `h(x) * t(x)` has degree below `2n` and vanishes on the even points,
+/// `tau^k * t(tau) = sum_i (g w^i)^k * t(g w^i) * L_{2i+1}(tau)`, and
+/// `t(g w^i) = g^n - 1 = -2`. Hence `ours_k = -2 g^k * FFT(H)_k`.
+fn read_h(
+    h: &[G1Affine],
+    domain: &EvaluationDomain,
+    layout: &Layout,
+) -> Result<Vec<G1Affine>, DecodeError> {
+    let h: Vec<G1Projective> = h.iter().map(G1Projective::from).collect();
+    let transformed = domain.fft(&h).map_err(|_| DecodeError::InvalidProgram)?;
+    let mut shift = -Scalar::from(2);
+    Ok((0..domain.size - 1)
+        .map(|k| {
+            let res = G1Affine::from(transformed[k] * shift);
+            shift *= layout.coset;
+            res
+        })
+        .collect())
+}
+
+/// The parts of the `.zkey` encoding that depend on the domain. Rows need no
+/// mapping, as `EvaluationDomain` uses snarkjs's roots of unity.
+struct Layout {
+    size: usize,
+    /// snarkjs's root of unity of the domain of size `2n`.
+    coset: Scalar,
+    /// `R^2` for the Montgomery radix `R = 2^256`, which the coefficients are
+    /// stored multiplied by.
+    montgomery_squared: Scalar,
+    montgomery_squared_inv: Scalar,
+}
+
+impl Layout {
+    fn new(domain: &EvaluationDomain) -> Self {
+        let montgomery_squared = Scalar::from(2).pow_vartime(&[512, 0, 0, 0]);
+        Layout {
+            size: domain.size,
+            coset: root_of_unity(domain.size.trailing_zeros() + 1),
+            montgomery_squared,
+            montgomery_squared_inv: montgomery_squared.invert().unwrap(),
+        }
+    }
+}
+
+/// Normalizes every row with `normalize_row` and pads the rows to `size`.
+fn normalize(rows: &[SparseRow], size: usize) -> Vec<SparseRow> {
+    let mut rows: Vec<SparseRow> = rows.iter().map(normalize_row).collect();
+    rows.resize(size, vec![]);
+    rows
+}
+
+fn write_modulus(bytes: &mut Vec<u8>, modulus: &[u8], size: usize) {
+    let mut modulus = modulus.to_vec();
+    modulus.resize(size, 0);
+    bytes.extend_from_slice(&(size as u32).to_le_bytes());
+    bytes.extend_from_slice(&modulus);
+}
+
+fn g1_bytes(points: &[G1Affine]) -> Vec<u8> {
+    let mut bytes = vec![];
+    points.iter().for_each(|p| binfile::write_g1(&mut bytes, p));
+    bytes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        field::from_i64, powers_of_tau::Accumulator, prover::Prover, r1cs, setup::keys_from_powers,
+        setup::TrustedSetup, verifier::Verifier, wtns,
+    };
+    use bls12_381::G2Affine;
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    fn program() -> Program {
+        // x^(i + 2) = x * x^(i + 1) for i < 5, over 8 rows once padded
+        let one = Scalar::one();
+        Program::new_sparse(
+            &(0..5).map(|_| vec![(1, one)]).collect::<Vec<_>>(),
+            &(0..5).map(|i| vec![(i + 1, one)]).collect::<Vec<_>>(),
+            &(0..5).map(|i| vec![(i + 2, one)]).collect::<Vec<_>>(),
+            7,
+            2,
+        )
+        .unwrap()
+    }
+
+    fn witness() -> Vec<Scalar> {
+        (0..7).map(|i| from_i64(3i64.pow(i))).collect()
+    }
+
+    #[test]
+    fn zkey_round_trip_and_prove() {
+        let program = program();
+        let keyed = with_input_rows(&program).unwrap();
+        let (pk, vk) = TrustedSetup::new_with_rng(&keyed, &mut ChaCha20Rng::seed_from_u64(1));
+        let bytes = write(&program, &pk, &vk).unwrap();
+        assert_eq!(read(&bytes), Ok((pk.clone(), vk.clone())));
+
+        let (pk, vk) = read_for_program(&bytes, &program).unwrap();
+        let witness = witness();
+        let proof = Prover::new(keyed, pk).unwrap().prove(&witness).unwrap();
+        assert_eq!(Verifier::new(vk).verify(&proof, &witness[1..2]), Ok(true));
+    }
+
+    #[test]
+    fn zkey_snarkjs_layout() {
+        // Keys from known toxic waste with gamma = delta = 1
+        let tau = Scalar::from(11);
+        let (alpha, beta) = (Scalar::from(3), Scalar::from(5));
+        let (g1, g2) = (G1Affine::generator(), G2Affine::generator());
+        let powers = |n: usize, scale: Scalar| -> Vec<Scalar> {
+            (0..n)
+                .map(|i| scale * tau.pow_vartime(&[i as u64, 0, 0, 0]))
+                .collect()
+        };
+        let accumulator = Accumulator {
+            tau_g1: powers(15, Scalar::one())
+                .iter()
+                .map(|x| (g1 * x).into())
+                .collect(),
+            tau_g2: powers(8, Scalar::one())
+                .iter()
+                .map(|x| (g2 * x).into())
+                .collect(),
+            alpha_tau_g1: powers(8, alpha).iter().map(|x| (g1 * x).into()).collect(),
+            beta_tau_g1: powers(8, beta).iter().map(|x| (g1 * x).into()).collect(),
+            beta_g2: (g2 * beta).into(),
+        };
+        let program = program();
+        let keyed = with_input_rows(&program).unwrap();
+        let (pk, vk) =
+            keys_from_powers(&keyed, &accumulator, Scalar::one(), Scalar::one()).unwrap();
+        let bytes = write(&program, &pk, &vk).unwrap();
+        let file = BinFile::parse(&bytes, MAGIC).unwrap();
+
+        // snarkjs's root of unity of order m, 5^((r - 1) / m)
+        let root = |m: usize| -> Scalar {
+            let mut limbs = ((r() - 1u8) / m).to_u64_digits();
+            limbs.resize(4, 0);
+            Scalar::from(5).pow_vartime(&limbs.try_into().unwrap())
+        };
+        let pow = |x: Scalar, k: usize| x.pow_vartime(&[k as u64, 0, 0, 0]);
+        let lagrange = |m: usize, j: usize| -> Scalar {
+            let omega_j = pow(root(m), j);
+            let t = pow(tau, m) - Scalar::one();
+            omega_j * t * (Scalar::from(m as u64) * (tau - omega_j)).invert().unwrap()
+        };
+
+        // H stands in for L_{2i+1}(tau) on the domain of size 2n, on every
+        // quotient of degree below n - 1: sum_i (g w^i)^k * -2 * H_i = tau^k * t(tau)
+        let h = file.section(SECTION_H).unwrap().g1_vec(8).unwrap();
+        for k in 0..7 {
+            let sum: G1Projective = (0..8)
+                .map(|i| h[i] * (pow(root(16), k * (2 * i + 1)) * -Scalar::from(2)))
+                .sum();
+            assert_eq!(
+                G1Affine::from(sum),
+                G1Affine::from(g1 * (pow(tau, k) * (pow(tau, 8) - Scalar::one())))
+            );
+        }
+
+        // A_i(tau) from the coefficients, with constraint j at snarkjs's w^j
+        let r_inv = (num_bigint::BigUint::from(1u8) << 512u32)
+            .modinv(r())
+            .unwrap();
+        let mut section = file.section(SECTION_COEFFS).unwrap();
+        let mut a = vec![Scalar::zero(); 7];
+        for _ in 0..section.u32().unwrap() {
+            let (matrix, row, col) = (
+                section.u32().unwrap(),
+                section.u32().unwrap(),
+                section.u32().unwrap(),
+            );
+            let raw = num_bigint::BigUint::from_bytes_le(section.take(FR_SIZE).unwrap());
+            let mut value = (raw * &r_inv % r()).to_bytes_le();
+            value.resize(32, 0);
+            let value = Scalar::from_bytes(&value.try_into().unwrap()).unwrap();
+            if matrix == 0 {
+                a[col as usize] += value * lagrange(8, row as usize);
+            }
+        }
+        let a: Vec<G1Affine> = a.iter().map(|x| (g1 * x).into()).collect();
+        assert_eq!(file.section(SECTION_A).unwrap().g1_vec(7).unwrap(), a);
+    }
+
+    #[test]
+    fn zkey_circom_input_constraints() {
+        // The keys carry snarkjs's input rows, A = w_s for each public
+        // signal s after the circuit's 2 constraints
+        let program = r1cs::read(include_bytes!("../fixtures/circom/product.r1cs"))
+            .unwrap()
+            .program;
+        let keyed = with_input_rows(&program).unwrap();
+        assert_eq!((program.left.len(), keyed.left.len()), (2, 5));
+        let (pk, vk) = TrustedSetup::new_with_rng(&keyed, &mut ChaCha20Rng::seed_from_u64(1));
+        let bytes = write(&program, &pk, &vk).unwrap();
+
+        let file = BinFile::parse(&bytes, MAGIC).unwrap();
+        let mut section = file.section(SECTION_COEFFS).unwrap();
+        let mut inputs = vec![];
+        for _ in 0..section.u32().unwrap() {
+            let entry = [0; 3].map(|_| section.u32().unwrap());
+            section.take(FR_SIZE).unwrap();
+            if entry[0] == 0 && entry[1] >= 2 {
+                inputs.push(entry);
+            }
+        }
+        assert_eq!(inputs, [[0, 2, 0], [0, 3, 1], [0, 4, 2]]);
+        // 2 + 3 rows put the key on a domain of 8, as snarkjs sizes it
+        assert_eq!(pk.t_tau_g1.len() + 1, 8);
+
+        let (pk, vk) = read_for_program(&bytes, &program).unwrap();
+        let witness =
+            wtns::read_for_program(include_bytes!("../fixtures/circom/product.wtns"), &program)
+                .unwrap();
+        let proof = Prover::new(keyed, pk).unwrap().prove(&witness).unwrap();
+        assert_eq!(Verifier::new(vk).verify(&proof, &witness[1..3]), Ok(true));
+    }
+
+    #[test]
+    fn zkey_errors() {
+        let program = program();
+        let keyed = with_input_rows(&program).unwrap();
+        let (pk, vk) = TrustedSetup::new_with_rng(&keyed, &mut ChaCha20Rng::seed_from_u64(1));
+        let bytes = write(&program, &pk, &vk).unwrap();
+
+        let other = Program::new_sparse(
+            &[vec![(1, Scalar::one())]],
+            &[vec![(1, Scalar::one())]],
+            &[vec![(2, Scalar::one())]],
+            7,
+            2,
+        )
+        .unwrap();
+        assert_eq!(write(&other, &pk, &vk), Err(Error::KeyMismatch));
+        assert_eq!(
+            read_for_program(&bytes, &other).err(),
+            Some(Error::KeyMismatch)
+        );
+
+        // snarkjs expects the public columns first
+        let mut reordered = self::program();
+        reordered.set_public(&[6]).unwrap();
+        let (pk, vk) = TrustedSetup::new_with_rng(&reordered, &mut ChaCha20Rng::seed_from_u64(1));
+        assert_eq!(write(&reordered, &pk, &vk), Err(Error::PublicNotPrefix));
+        assert_eq!(
+            read_for_program(&bytes, &reordered).err(),
+            Some(Error::PublicNotPrefix)
+        );
+
+        assert_eq!(read(b"ptau"), Err(DecodeError::BadMagic));
+        assert_eq!(read(&bytes[..200]), Err(DecodeError::UnexpectedEof));
+        let mut plonk = bytes.clone();
+        plonk[24] = 2;
+        assert_eq!(read(&plonk), Err(DecodeError::UnsupportedProtocol));
+    }
+}